@@ -0,0 +1,395 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use std::marker::PhantomData;
+
+use boojum::{
+    cs::{
+        cs_builder::{CsBuilder, CsBuilderImpl},
+        gates::PublicInputGate,
+        implementations::{pow::NoPow, proof::Proof, verifier::VerificationKey},
+        oracle::TreeHasher,
+        traits::cs::ConstraintSystem,
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    field::{Field, U64Representable},
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        recursion::recursive_tree_hasher::{CircuitGoldilocksPoseidonSponge, CircuitTreeHasher},
+        traits::selectable::Selectable,
+    },
+    worker::Worker,
+};
+use common::{
+    demos::{DemoCircuit, SimplePolyDemo, F},
+    harness::{prove, synthesize_assembly, verify, ProofProfile},
+    recursion::{
+        configure_recursion, verify_in_circuit, EXT, H, RECURSION_GEOMETRY,
+        RECURSION_MAX_TRACE_LEN, RECURSION_MAX_VARIABLES, TR,
+    },
+};
+
+type Digest = [F; 4];
+
+// 某一层的节点个数为奇数时，用这个摘要补齐
+const EMPTY_DIGEST: Digest = [F::ZERO; 4];
+
+fn leaf_digest(public_inputs: &[F]) -> Digest {
+    <H as TreeHasher<F>>::hash_into_leaf(public_inputs)
+}
+
+fn node_digest(left: &Digest, right: &Digest) -> Digest {
+    <H as TreeHasher<F>>::hash_into_leaf(&[left.as_slice(), right.as_slice()].concat())
+}
+
+// 对N个proof的public input的承诺：
+// 叶子是每个proof的public input的哈希，每层两两合并，节点数为奇数时补一个EMPTY_DIGEST，
+// 与聚合树中每个电路的计算顺序一致
+fn aggregation_commitment(public_inputs: &[Vec<F>]) -> Digest {
+    assert!(!public_inputs.is_empty());
+
+    let mut level: Vec<Digest> = public_inputs
+        .iter()
+        .map(|inputs| leaf_digest(inputs))
+        .collect();
+    loop {
+        if level.len() % 2 == 1 {
+            level.push(EMPTY_DIGEST);
+        }
+        level = level
+            .chunks_exact(2)
+            .map(|pair| node_digest(&pair[0], &pair[1]))
+            .collect();
+        if level.len() == 1 {
+            return level[0];
+        }
+    }
+}
+
+// 聚合树中的一个节点：在电路中验证两个proof，把它们的承诺合并后作为public input
+// level = 0 时子节点是D的proof，否则子节点是上一层聚合电路的proof
+// is_padding的子节点只是为了补齐而重复的proof，它的摘要替换为EMPTY_DIGEST。
+// is_padding由叶子的个数决定，作为常量写进电路（因此也在vk中），prover不能选择；
+// 所以同一层中补齐的节点与其他节点的vk不同，每个子节点使用自己的vk
+struct AggregationNode<D: DemoCircuit> {
+    level: usize,
+    inner_geometry: CSGeometry,
+    vks: [VerificationKey<F, H>; 2],
+    children: [Proof<F, H, EXT>; 2],
+    is_padding: [bool; 2],
+    _marker: PhantomData<D>,
+}
+
+impl<D: DemoCircuit> AggregationNode<D> {
+    fn child_digest(&self, child: usize) -> Digest {
+        if self.is_padding[child] {
+            EMPTY_DIGEST
+        } else if self.level == 0 {
+            leaf_digest(&self.children[child].public_inputs)
+        } else {
+            self.children[child]
+                .public_inputs
+                .clone()
+                .try_into()
+                .unwrap()
+        }
+    }
+}
+
+impl<D: DemoCircuit> DemoCircuit for AggregationNode<D> {
+    fn name(&self) -> &'static str {
+        "aggregation_node"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        RECURSION_GEOMETRY
+    }
+
+    fn max_variables(&self) -> usize {
+        RECURSION_MAX_VARIABLES
+    }
+
+    fn max_trace_len(&self) -> usize {
+        RECURSION_MAX_TRACE_LEN
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        configure_recursion(builder)
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let empty = EMPTY_DIGEST.map(|el| Num::allocated_constant(cs, el));
+
+        let mut digests = vec![];
+        for ((proof, vk), is_padding) in self.children.iter().zip(&self.vks).zip(self.is_padding) {
+            let digest = if self.level == 0 {
+                let public_inputs = verify_in_circuit::<D, CS>(cs, self.inner_geometry, vk, proof);
+                CircuitGoldilocksPoseidonSponge::hash_into_leaf(cs, public_inputs.iter())
+            } else {
+                let public_inputs =
+                    verify_in_circuit::<Self, CS>(cs, self.inner_geometry, vk, proof);
+                public_inputs
+                    .try_into()
+                    .expect("聚合电路的public input是一个摘要")
+            };
+
+            let is_padding = Boolean::allocated_constant(cs, is_padding);
+            let digest: [Num<F>; 4] = std::array::from_fn(|i| {
+                Num::conditionally_select(cs, is_padding, &empty[i], &digest[i])
+            });
+            digests.extend(digest);
+        }
+
+        let commitment = CircuitGoldilocksPoseidonSponge::hash_into_leaf(cs, digests.iter());
+        for el in commitment {
+            let gate = PublicInputGate::new(el.get_variable());
+            gate.add_to_cs(cs);
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        node_digest(&self.child_digest(0), &self.child_digest(1)).to_vec()
+    }
+}
+
+struct AggregatedProof<D: DemoCircuit> {
+    proof: Proof<F, H, EXT>,
+    vk: VerificationKey<F, H>,
+    // 根节点的电路，用于验证
+    root: AggregationNode<D>,
+    levels: usize,
+}
+
+// 二叉树聚合任意N个使用同一个vk的proof
+// 每个节点的电路只由叶子的个数决定，所以根节点的vk对于固定的N是确定的
+fn aggregate<D: DemoCircuit>(
+    inner_geometry: CSGeometry,
+    vk: VerificationKey<F, H>,
+    proofs: Vec<Proof<F, H, EXT>>,
+    worker: &Worker,
+) -> AggregatedProof<D> {
+    assert!(!proofs.is_empty());
+
+    let mut vks = vec![vk; proofs.len()];
+    let mut proofs = proofs;
+    let mut inner_geometry = inner_geometry;
+    let mut level = 0;
+
+    loop {
+        let mut next_proofs = vec![];
+        let mut next_vks = vec![];
+        let mut last_node = None;
+
+        for (pair, pair_vks) in proofs.chunks(2).zip(vks.chunks(2)) {
+            let (children, vks, is_padding) = match (pair, pair_vks) {
+                ([left, right], [left_vk, right_vk]) => (
+                    [left.clone(), right.clone()],
+                    [left_vk.clone(), right_vk.clone()],
+                    [false, false],
+                ),
+                ([single], [single_vk]) => (
+                    [single.clone(), single.clone()],
+                    [single_vk.clone(), single_vk.clone()],
+                    [false, true],
+                ),
+                _ => unreachable!(),
+            };
+            let node = AggregationNode::<D> {
+                level,
+                inner_geometry,
+                vks,
+                children,
+                is_padding,
+                _marker: PhantomData,
+            };
+
+            let (proof, node_vk) =
+                prove::<_, TR, H, NoPow>(&node, worker, ProofProfile::Demo.proof_config());
+            next_proofs.push(proof);
+            next_vks.push(node_vk);
+            last_node = Some(node);
+        }
+
+        proofs = next_proofs;
+        vks = next_vks;
+        inner_geometry = RECURSION_GEOMETRY;
+        level += 1;
+
+        if proofs.len() == 1 {
+            return AggregatedProof {
+                proof: proofs.pop().unwrap(),
+                vk: vks.pop().unwrap(),
+                root: last_node.unwrap(),
+                levels: level,
+            };
+        }
+    }
+}
+
+fn simple_poly(x: u64) -> SimplePolyDemo {
+    SimplePolyDemo {
+        x,
+        out: x * x * x + x + 5,
+    }
+}
+
+fn simple_poly_proofs(
+    xs: &[u64],
+    worker: &Worker,
+) -> (VerificationKey<F, H>, Vec<Proof<F, H, EXT>>) {
+    let mut vk = None;
+    let mut proofs = vec![];
+    for x in xs {
+        let (proof, proof_vk) =
+            prove::<_, TR, H, NoPow>(&simple_poly(*x), worker, ProofProfile::Demo.proof_config());
+        proofs.push(proof);
+        vk = Some(proof_vk);
+    }
+
+    (vk.unwrap(), proofs)
+}
+
+#[test]
+fn aggregation_commitment_shape() {
+    let inputs: Vec<Vec<F>> = (1..=5).map(|x| simple_poly(x).public_inputs()).collect();
+
+    let leaves: Vec<Digest> = inputs.iter().map(|inputs| leaf_digest(inputs)).collect();
+    assert_eq!(
+        aggregation_commitment(&inputs[..1]),
+        node_digest(&leaves[0], &EMPTY_DIGEST)
+    );
+    assert_eq!(
+        aggregation_commitment(&inputs[..3]),
+        node_digest(
+            &node_digest(&leaves[0], &leaves[1]),
+            &node_digest(&leaves[2], &EMPTY_DIGEST)
+        )
+    );
+
+    // 顺序和个数都会改变承诺
+    let mut reordered = inputs.clone();
+    reordered.swap(0, 1);
+    assert_ne!(
+        aggregation_commitment(&inputs),
+        aggregation_commitment(&reordered)
+    );
+    assert_ne!(
+        aggregation_commitment(&inputs[..4]),
+        aggregation_commitment(&inputs)
+    );
+}
+
+#[test]
+fn aggregation_node_with_padding() {
+    let worker = Worker::new_with_num_threads(8);
+    let (vk, proofs) = simple_poly_proofs(&[7], &worker);
+
+    let node = AggregationNode::<SimplePolyDemo> {
+        level: 0,
+        inner_geometry: simple_poly(7).geometry(),
+        vks: [vk.clone(), vk],
+        children: [proofs[0].clone(), proofs[0].clone()],
+        is_padding: [false, true],
+        _marker: PhantomData,
+    };
+    assert_eq!(
+        node.public_inputs(),
+        aggregation_commitment(&[simple_poly(7).public_inputs()]).to_vec()
+    );
+
+    let mut cs = synthesize_assembly(&node);
+    assert!(cs.check_if_satisfied(&worker));
+
+    // 子proof无效时电路不能满足
+    let mut tampered = proofs[0].clone();
+    tampered.public_inputs[0] =
+        F::from_u64_unchecked(tampered.public_inputs[0].as_u64_reduced() + 1);
+    let node = AggregationNode::<SimplePolyDemo> {
+        children: [tampered, proofs[0].clone()],
+        ..node
+    };
+    let mut cs = synthesize_assembly(&node);
+    assert!(cs.check_if_satisfied(&worker) == false);
+}
+
+#[test]
+fn aggregate_four_simple_poly_proofs() {
+    let worker = Worker::new_with_num_threads(8);
+
+    let xs = [1, 2, 3, 4];
+    let (vk, proofs) = simple_poly_proofs(&xs, &worker);
+
+    let aggregated = aggregate::<SimplePolyDemo>(simple_poly(1).geometry(), vk, proofs, &worker);
+    assert_eq!(aggregated.levels, 2);
+
+    // 聚合后的proof的public input是所有public input的承诺（4个元素的摘要）
+    let inputs: Vec<Vec<F>> = xs.iter().map(|x| simple_poly(*x).public_inputs()).collect();
+    assert_eq!(
+        aggregated.proof.public_inputs,
+        aggregation_commitment(&inputs).to_vec()
+    );
+
+    assert!(verify::<_, TR, H, NoPow>(
+        &aggregated.root,
+        &aggregated.vk,
+        &aggregated.proof
+    ));
+}
+
+#[test]
+fn real_child_cannot_be_marked_as_padding() {
+    let worker = Worker::new_with_num_threads(8);
+    let (vk, proofs) = simple_poly_proofs(&[3, 4], &worker);
+
+    let honest = AggregationNode::<SimplePolyDemo> {
+        level: 0,
+        inner_geometry: simple_poly(3).geometry(),
+        vks: [vk.clone(), vk],
+        children: [proofs[0].clone(), proofs[1].clone()],
+        is_padding: [false, false],
+        _marker: PhantomData,
+    };
+    // 把真实的第二个proof标记为补齐，它的public input不会进入承诺
+    let cheating = AggregationNode::<SimplePolyDemo> {
+        is_padding: [false, true],
+        vks: honest.vks.clone(),
+        children: honest.children.clone(),
+        ..honest
+    };
+    let inputs = [
+        simple_poly(3).public_inputs(),
+        simple_poly(4).public_inputs(),
+    ];
+    assert_eq!(
+        honest.public_inputs(),
+        aggregation_commitment(&inputs).to_vec()
+    );
+    assert_ne!(
+        cheating.public_inputs(),
+        aggregation_commitment(&inputs).to_vec()
+    );
+
+    // is_padding是电路中的常量，两个叶子的节点的vk不接受标记了补齐的proof
+    let (honest_proof, honest_vk) =
+        prove::<_, TR, H, NoPow>(&honest, &worker, ProofProfile::Demo.proof_config());
+    let (cheating_proof, cheating_vk) =
+        prove::<_, TR, H, NoPow>(&cheating, &worker, ProofProfile::Demo.proof_config());
+    assert_ne!(
+        honest_vk.setup_merkle_tree_cap,
+        cheating_vk.setup_merkle_tree_cap
+    );
+    assert!(verify::<_, TR, H, NoPow>(
+        &honest,
+        &honest_vk,
+        &honest_proof
+    ));
+    assert!(verify::<_, TR, H, NoPow>(&honest, &honest_vk, &cheating_proof) == false);
+}
@@ -0,0 +1,96 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use std::time::Instant;
+
+use boojum::{cs::implementations::pow::NoPow, field::U64Representable, worker::Worker};
+use common::{
+    demos::{FibonacciDemo, F},
+    harness::{prove, verify, PreparedVerifier, ProofProfile},
+    recursion::{H, TR},
+};
+
+#[test]
+fn batch_verify_reports_each_proof() {
+    let worker = Worker::new_with_num_threads(8);
+    let demo = FibonacciDemo::default();
+    let (proof, vk) = prove::<_, TR, H, NoPow>(&demo, &worker, ProofProfile::Demo.proof_config());
+
+    let mut proofs = vec![proof.clone(); 10];
+    // 修改其中两个proof
+    proofs[3].public_inputs[0] = F::from_u64_unchecked(35);
+    let leaf = &mut proofs[7].queries_per_fri_repetition[0]
+        .witness_query
+        .leaf_elements[0];
+    *leaf = F::from_u64_unchecked(leaf.as_u64_reduced() ^ 1);
+
+    let verifier = PreparedVerifier::new(&demo, vk);
+    let results = verifier.verify_batch::<TR, NoPow>(&proofs, &worker);
+    let expected: Vec<bool> = (0..proofs.len()).map(|i| i != 3 && i != 7).collect();
+    assert_eq!(results, expected);
+
+    // 与逐个验证的结果一致
+    let serial: Vec<bool> = proofs
+        .iter()
+        .map(|proof| verifier.verify::<TR, NoPow>(proof))
+        .collect();
+    assert_eq!(results, serial);
+
+    assert!(verifier.verify_batch::<TR, NoPow>(&[], &worker).is_empty());
+}
+
+// 性能测试，默认不运行：cargo test --test batch_verify_demo -- --ignored --nocapture
+#[test]
+#[ignore]
+fn batch_verify_throughput() {
+    let worker = Worker::new_with_num_threads(8);
+    let demo = FibonacciDemo::default();
+    let (proof, vk) = prove::<_, TR, H, NoPow>(&demo, &worker, ProofProfile::Demo.proof_config());
+
+    let num_proofs = 64;
+    let proofs = vec![proof; num_proofs];
+
+    // 原来的方式：每次验证都重新构建verifier
+    let start = Instant::now();
+    for proof in &proofs {
+        assert!(verify::<_, TR, H, NoPow>(&demo, &vk, proof));
+    }
+    let one_at_a_time = start.elapsed();
+
+    let start = Instant::now();
+    let verifier = PreparedVerifier::new(&demo, vk);
+    let build_time = start.elapsed();
+
+    let start = Instant::now();
+    for proof in &proofs {
+        assert!(verifier.verify::<TR, NoPow>(proof));
+    }
+    let prepared = start.elapsed();
+
+    let start = Instant::now();
+    let results = verifier.verify_batch::<TR, NoPow>(&proofs, &worker);
+    let batch = start.elapsed();
+    assert!(results.into_iter().all(|is_valid| is_valid));
+
+    let throughput = |elapsed: std::time::Duration| num_proofs as f64 / elapsed.as_secs_f64();
+    println!("{} proofs, verifier build {:?}", num_proofs, build_time);
+    println!(
+        "{:<16} {:>12.3?} {:>10.1} proofs/s",
+        "one at a time",
+        one_at_a_time,
+        throughput(one_at_a_time)
+    );
+    println!(
+        "{:<16} {:>12.3?} {:>10.1} proofs/s",
+        "prepared",
+        prepared,
+        throughput(prepared)
+    );
+    println!(
+        "{:<16} {:>12.3?} {:>10.1} proofs/s",
+        "batch (8 threads)",
+        batch,
+        throughput(batch)
+    );
+}
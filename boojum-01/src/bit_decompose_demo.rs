@@ -0,0 +1,415 @@
+#![feature(allocator_api)]
+use std::alloc::Global;
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidonSponge},
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            BooleanConstraintGate, ConstantAllocatableCS, ConstantsAllocatorGate,
+            FmaGateInBaseFieldWithoutConstant, FmaGateInBaseWithoutConstantParams, NopGate,
+            PublicInputGate, ReductionGate, ZeroCheckGate,
+        },
+        implementations::{
+            pow::NoPow, prover::ProofConfig, transcript::GoldilocksPoisedonTranscript,
+        },
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, LookupParameters, StaticToolboxHolder, Variable,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        Field, SmallField, U64Representable,
+    },
+    gadgets::tables::{create_xor8_table, Xor8Table},
+    worker::Worker,
+};
+
+// Goldilocks 模数 p = 2^64 - 2^32 + 1
+// 64 位的分解可以表示 [0, 2^64) 中的任意整数，而 [p, 2^64) 中的值 v 与 v - p 在域中相等。
+// 如果不检查分解是否小于 p，证明者就可以给同一个域元素提供两种不同的分解。
+const GOLDILOCKS_MODULUS: u64 = 0xFFFF_FFFF_0000_0001;
+
+// 把若干个已知是布尔值（或字节）的variable按小端序合并成一个variable
+// 每个ReductionGate处理4项：第一个门放4个分量，之后每个门放上一个门的结果和3个新分量
+fn recompose_le<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    limbs: &[Variable],
+    limb_width: usize,
+) -> Variable {
+    assert!(!limbs.is_empty());
+    assert!(limbs.len() * limb_width <= 64);
+
+    let zero = cs.allocate_constant(F::ZERO);
+    let shift = |i: usize| F::from_u64_unchecked(1u64 << (i * limb_width));
+
+    let mut constants = [F::ZERO; 4];
+    let mut terms = [zero; 4];
+    for (i, limb) in limbs.iter().take(4).enumerate() {
+        constants[i] = shift(i);
+        terms[i] = *limb;
+    }
+    let mut acc = ReductionGate::reduce_terms(cs, constants, terms);
+
+    for (chunk_idx, chunk) in limbs[4.min(limbs.len())..].chunks(3).enumerate() {
+        let offset = 4 + chunk_idx * 3;
+        let mut constants = [F::ONE, F::ZERO, F::ZERO, F::ZERO];
+        let mut terms = [acc, zero, zero, zero];
+        for (i, limb) in chunk.iter().enumerate() {
+            constants[i + 1] = shift(offset + i);
+            terms[i + 1] = *limb;
+        }
+        acc = ReductionGate::reduce_terms(cs, constants, terms);
+    }
+
+    acc
+}
+
+// 约束 x = lo + 2^32 * hi，并检查 hi * 2^32 + lo < p
+// 因为 p - 1 = 0xFFFFFFFF_00000000，所以不合法的分解当且仅当 hi == 2^32 - 1 且 lo != 0
+fn enforce_canonical_split<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    x: Variable,
+    lo: Variable,
+    hi: Variable,
+    check_canonicity: bool,
+) {
+    let one = cs.allocate_constant(F::ONE);
+    let zero = cs.allocate_constant(F::ZERO);
+
+    // x = 2^32 * (hi * 1) + 1 * lo
+    let gate = FmaGateInBaseFieldWithoutConstant {
+        params: FmaGateInBaseWithoutConstantParams {
+            coeff_for_quadtaric_part: F::from_u64_unchecked(1u64 << 32),
+            linear_term_coeff: F::ONE,
+        },
+        quadratic_part: (hi, one),
+        linear_part: lo,
+        rhs_part: x,
+    };
+    gate.add_to_cs(cs);
+
+    if check_canonicity == false {
+        return;
+    }
+
+    // hi - (2^32 - 1) = 1 * (hi * 1) + (-(2^32 - 1)) * 1
+    let mut minus_max_hi = F::from_u64_unchecked(u32::MAX as u64);
+    minus_max_hi.negate();
+    let hi_diff =
+        FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (hi, one), minus_max_hi, one);
+
+    let hi_is_all_ones = ZeroCheckGate::check_if_zero(cs, hi_diff);
+    let lo_is_zero = ZeroCheckGate::check_if_zero(cs, lo);
+
+    // hi_is_all_ones * (1 - lo_is_zero) = 0
+    // 即 hi_is_all_ones * lo_is_zero - hi_is_all_ones = 0
+    let gate = FmaGateInBaseFieldWithoutConstant {
+        params: FmaGateInBaseWithoutConstantParams {
+            coeff_for_quadtaric_part: F::ONE,
+            linear_term_coeff: F::MINUS_ONE,
+        },
+        quadratic_part: (hi_is_all_ones, lo_is_zero),
+        linear_part: hi_is_all_ones,
+        rhs_part: zero,
+    };
+    gate.add_to_cs(cs);
+}
+
+// 按给定的整数分解 x，witness由调用者提供，这样负面测试可以故意提供错误的分解
+fn to_bits_le_with_witness<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    x: Variable,
+    decomposition: u64,
+    check_canonicity: bool,
+) -> [Variable; 64] {
+    let bits: [Variable; 64] = std::array::from_fn(|i| {
+        BooleanConstraintGate::alloc_boolean_from_witness(cs, (decomposition >> i) & 1 == 1)
+    });
+
+    let lo = recompose_le(cs, &bits[..32], 1);
+    let hi = recompose_le(cs, &bits[32..], 1);
+    enforce_canonical_split(cs, x, lo, hi, check_canonicity);
+
+    bits
+}
+
+// 把Goldilocks元素分解成64个小端序的比特，并证明分解是唯一的（小于p）
+fn to_bits_le<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    x: Variable,
+    x_value: F,
+) -> [Variable; 64] {
+    to_bits_le_with_witness(cs, x, x_value.as_u64_reduced(), true)
+}
+
+// 把不超过64个比特合并成一个域元素，调用者需要保证输入已经约束为布尔值
+// 超过63位时结果在域中取模，需要唯一性时请使用 to_bits_le 的范围检查
+fn from_bits_le<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    bits: &[Variable],
+) -> Variable {
+    recompose_le(cs, bits, 1)
+}
+
+// 字节分解：每两个字节做一次xor8查找，查找成功就说明两个字节都在 [0, 256) 中
+fn to_bytes_le_with_witness<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    x: Variable,
+    decomposition: u64,
+    check_canonicity: bool,
+    xor8_table_id: u32,
+) -> [Variable; 8] {
+    let bytes: [Variable; 8] = std::array::from_fn(|i| {
+        cs.alloc_single_variable_from_witness(F::from_u64_unchecked(
+            (decomposition >> (8 * i)) & 0xff,
+        ))
+    });
+
+    for pair in bytes.chunks(2) {
+        let [_xor] = cs.perform_lookup::<2, 1>(xor8_table_id, &[pair[0], pair[1]]);
+    }
+
+    let lo = recompose_le(cs, &bytes[..4], 8);
+    let hi = recompose_le(cs, &bytes[4..], 8);
+    enforce_canonical_split(cs, x, lo, hi, check_canonicity);
+
+    bytes
+}
+
+fn to_bytes_le<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    x: Variable,
+    x_value: F,
+    xor8_table_id: u32,
+) -> [Variable; 8] {
+    to_bytes_le_with_witness(cs, x, x_value.as_u64_reduced(), true, xor8_table_id)
+}
+
+// 设置电路参数
+const GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 20,
+    num_witness_columns: 0,
+    num_constant_columns: 4,
+    max_allowed_constraint_degree: 8,
+};
+
+const MAX_VARIABLES: usize = 1 << 12; // variable数量上限
+const MAX_TRACE_LEN: usize = 1 << 10; // 电路表格的行数上限
+
+// 配置cs的函数
+fn configure<
+    F: SmallField,
+    T: CsBuilderImpl<F, T>,
+    GC: GateConfigurationHolder<F>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    // xor8表的宽度为3：两个输入字节和一个结果
+    let builder = builder.allow_lookup(
+        LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+            width: 3,
+            num_repetitions: 4,
+            share_table_id: true,
+        },
+    );
+    let builder = BooleanConstraintGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = ReductionGate::<F, 4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 最后一个参数表示不使用只检查一个变量的特殊版本
+    let builder = ZeroCheckGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+        false,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 在cs中加入空操作门，用于pad_and_shrink
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Decomposition {
+    Bits,
+    Bytes,
+}
+
+// 对 x 用给定的整数做分解，返回约束是否全部满足
+fn decomposition_is_satisfied(
+    kind: Decomposition,
+    x: u64,
+    decomposition: u64,
+    check_canonicity: bool,
+) -> bool {
+    type P = GoldilocksField;
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    let table_id = cs.add_lookup_table::<Xor8Table, 3>(create_xor8_table());
+
+    let x = cs.alloc_single_variable_from_witness(GoldilocksField::from_u64_unchecked(x));
+    match kind {
+        Decomposition::Bits => {
+            to_bits_le_with_witness(&mut cs, x, decomposition, check_canonicity);
+        }
+        Decomposition::Bytes => {
+            to_bytes_le_with_witness(&mut cs, x, decomposition, check_canonicity, table_id);
+        }
+    }
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(1);
+    let mut cs = cs.into_assembly::<Global>();
+    cs.check_if_satisfied(&worker)
+}
+
+#[test]
+fn bit_decompose_demo() {
+    type P = GoldilocksField;
+
+    // cs builder: 约束系统的工厂类
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    let table_id = cs.add_lookup_table::<Xor8Table, 3>(create_xor8_table());
+
+    // 最大的合法值 p - 1，它的高32位全是1
+    let x_value = GoldilocksField::from_u64_unchecked(GOLDILOCKS_MODULUS - 1);
+    let x = cs.alloc_single_variable_from_witness(x_value);
+
+    // 分解成比特再合并回来，结果作为public input
+    let bits = to_bits_le(&mut cs, x, x_value);
+    let recomposed = from_bits_le(&mut cs, &bits);
+    let gate = PublicInputGate::new(recomposed);
+    gate.add_to_cs(&mut cs);
+
+    // 字节分解
+    let bytes = to_bytes_le(&mut cs, x, x_value, table_id);
+    let gate = PublicInputGate::new(bytes[7]);
+    gate.add_to_cs(&mut cs);
+
+    // optional
+    cs.pad_and_shrink();
+
+    // 设置线程数量
+    let worker = Worker::new_with_num_threads(1);
+    let mut cs = cs.into_assembly::<Global>();
+
+    assert!(cs.check_if_satisfied(&worker));
+
+    // FRI 的 LDE 因子
+    let lde_factor_to_use = 16;
+    let proof_config = ProofConfig {
+        fri_lde_factor: lde_factor_to_use,
+        pow_bits: 0,
+        merkle_tree_cap_size: 4,
+        ..Default::default()
+    };
+
+    // 使用prove_one_shot同时生成proof和vk，在生产环境不建议这样使用
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    assert_eq!(
+        proof.public_inputs,
+        vec![x_value, GoldilocksField::from_u64_unchecked(0xff)]
+    );
+
+    let builder_impl =
+        CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(GEOMETRY);
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
+
+#[test]
+fn canonical_decompositions_are_accepted() {
+    for x in [0, 1, 5, u32::MAX as u64, 1 << 32, GOLDILOCKS_MODULUS - 1] {
+        assert!(decomposition_is_satisfied(Decomposition::Bits, x, x, true));
+        assert!(decomposition_is_satisfied(Decomposition::Bytes, x, x, true));
+    }
+}
+
+#[test]
+fn non_canonical_decompositions_are_rejected() {
+    // x + p 仍然小于 2^64，并且在域中等于 x
+    for x in [0, 5, u32::MAX as u64 - 1] {
+        let aliased = x + GOLDILOCKS_MODULUS;
+        assert!(decomposition_is_satisfied(Decomposition::Bits, x, aliased, true) == false);
+        assert!(decomposition_is_satisfied(Decomposition::Bytes, x, aliased, true) == false);
+
+        // 没有范围检查时，别名分解可以通过，这就是需要检查的原因
+        assert!(decomposition_is_satisfied(
+            Decomposition::Bits,
+            x,
+            aliased,
+            false
+        ));
+        assert!(decomposition_is_satisfied(
+            Decomposition::Bytes,
+            x,
+            aliased,
+            false
+        ));
+    }
+}
+
+#[test]
+fn wrong_decompositions_are_rejected() {
+    let x = 0x1234_5678_9abc_def0;
+    assert!(decomposition_is_satisfied(Decomposition::Bits, x, x ^ 1, true) == false);
+    assert!(decomposition_is_satisfied(Decomposition::Bits, x, x ^ (1 << 63), true) == false);
+    assert!(decomposition_is_satisfied(Decomposition::Bytes, x, x ^ 0x100, true) == false);
+}
@@ -0,0 +1,585 @@
+#![feature(allocator_api)]
+use std::alloc::Global;
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidonSponge},
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            BooleanConstraintGate, ConstantsAllocatorGate, DotProductGate,
+            FmaGateInBaseFieldWithoutConstant, NopGate, PublicInputGate, ReductionGate,
+            SelectionGate, UIntXAddGate, ZeroCheckGate,
+        },
+        implementations::{
+            lookup_table::LookupTable, pow::NoPow, prover::ProofConfig,
+            transcript::GoldilocksPoisedonTranscript,
+        },
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, LookupParameters, StaticToolboxHolder, Variable,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        Field, SmallField, U64Representable,
+    },
+    gadgets::{
+        num::Num,
+        tables::{create_xor8_table, Xor8Table},
+        u32::UInt32,
+        u8::UInt8,
+    },
+    worker::Worker,
+};
+use derivative::Derivative;
+
+// Blake2s 的IV与SHA-256的初始状态相同，RFC 7693 2.6
+const BLAKE2S_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+// 每一轮消息字的排列，RFC 7693 2.7
+const BLAKE2S_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+const BLAKE2S_BLOCK_SIZE: usize = 64;
+const BLAKE2S_OUTPUT_SIZE: usize = 32;
+
+// 循环右移查找表：(byte, s) -> (byte >> s, (byte << (8 - s)) & 0xff)，s 取 1..8
+// 一个字节右移s位后，高位部分留在本字节，低位部分进入前一个字节
+pub const BLAKE2S_ROTATION_TABLE_NAME: &str = "Blake2s rotation table";
+
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug)]
+pub struct Blake2sRotationTableMarker;
+
+fn create_rotation_table<F: SmallField>() -> LookupTable<F, 4> {
+    let mut all_keys = Vec::with_capacity(256 * 7);
+    for byte in 0..256 {
+        for shift in 1..8 {
+            let key = smallvec::smallvec![
+                F::from_u64_unchecked(byte as u64),
+                F::from_u64_unchecked(shift as u64)
+            ];
+            all_keys.push(key);
+        }
+    }
+    LookupTable::new_from_keys_and_generation_function(
+        &all_keys,
+        BLAKE2S_ROTATION_TABLE_NAME.to_string(),
+        2,
+        |keys| {
+            let byte = keys[0].as_u64_reduced();
+            let shift = keys[1].as_u64_reduced();
+
+            let high_part = byte >> shift;
+            let low_part = (byte << (8 - shift)) & 0xff;
+
+            smallvec::smallvec![
+                F::from_u64_unchecked(high_part),
+                F::from_u64_unchecked(low_part)
+            ]
+        },
+    )
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Blake2sTableIds {
+    xor8: u32,
+    rotation: u32,
+}
+
+fn add_blake2s_tables<F: SmallField, CS: ConstraintSystem<F>>(cs: &mut CS) -> Blake2sTableIds {
+    let xor8 = cs.add_lookup_table::<Xor8Table, 3>(create_xor8_table());
+    let rotation = cs.add_lookup_table::<Blake2sRotationTableMarker, 4>(create_rotation_table());
+
+    Blake2sTableIds { xor8, rotation }
+}
+
+fn add_words<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    a: &UInt32<F>,
+    b: &UInt32<F>,
+) -> UInt32<F> {
+    // 模 2^32 加法，丢弃进位
+    let (sum, _carry) = a.overflowing_add(cs, b);
+    sum
+}
+
+fn xor_words<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: Blake2sTableIds,
+    a: &UInt32<F>,
+    b: &UInt32<F>,
+) -> [Variable; 4] {
+    let a = a.to_le_bytes(cs);
+    let b = b.to_le_bytes(cs);
+    std::array::from_fn(|i| {
+        let [xor] =
+            cs.perform_lookup::<2, 1>(tables.xor8, &[a[i].get_variable(), b[i].get_variable()]);
+        xor
+    })
+}
+
+fn word_from_bytes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    bytes: [Variable; 4],
+) -> UInt32<F> {
+    // 这些字节都是查找表的输出，已经在 [0, 256) 中
+    let bytes = bytes.map(|byte| unsafe { UInt8::from_variable_unchecked(byte) });
+    UInt32::from_le_bytes(cs, bytes)
+}
+
+// rotr(a ^ b, amount)：整字节的部分只重新排列字节，剩下的比特通过查找表拆分后相加
+fn xor_rotate_right<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: Blake2sTableIds,
+    a: &UInt32<F>,
+    b: &UInt32<F>,
+    amount: u32,
+) -> UInt32<F> {
+    let bytes = xor_words(cs, tables, a, b);
+
+    let byte_shift = (amount / 8) as usize;
+    let bit_shift = amount % 8;
+
+    let bytes: [Variable; 4] = std::array::from_fn(|i| bytes[(i + byte_shift) % 4]);
+    if bit_shift == 0 {
+        return word_from_bytes(cs, bytes);
+    }
+
+    let shift = cs.allocate_constant(F::from_u64_unchecked(bit_shift as u64));
+    let one = cs.allocate_constant(F::ONE);
+    let parts: [[Variable; 2]; 4] =
+        std::array::from_fn(|i| cs.perform_lookup::<2, 2>(tables.rotation, &[bytes[i], shift]));
+    let rotated: [Variable; 4] = std::array::from_fn(|i| {
+        let [high_part, _] = parts[i];
+        let [_, low_part] = parts[(i + 1) % 4];
+        // high_part * 1 + low_part
+        FmaGateInBaseFieldWithoutConstant::compute_fma(
+            cs,
+            F::ONE,
+            (high_part, one),
+            F::ONE,
+            low_part,
+        )
+    });
+
+    word_from_bytes(cs, rotated)
+}
+
+// 混合函数G，RFC 7693 3.1
+fn mixing_function<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: Blake2sTableIds,
+    v: &mut [UInt32<F>; 16],
+    [a, b, c, d]: [usize; 4],
+    x: &UInt32<F>,
+    y: &UInt32<F>,
+) {
+    let t = add_words(cs, &v[a], &v[b]);
+    v[a] = add_words(cs, &t, x);
+    v[d] = xor_rotate_right(cs, tables, &v[d], &v[a], 16);
+    v[c] = add_words(cs, &v[c], &v[d]);
+    v[b] = xor_rotate_right(cs, tables, &v[b], &v[c], 12);
+
+    let t = add_words(cs, &v[a], &v[b]);
+    v[a] = add_words(cs, &t, y);
+    v[d] = xor_rotate_right(cs, tables, &v[d], &v[a], 8);
+    v[c] = add_words(cs, &v[c], &v[d]);
+    v[b] = xor_rotate_right(cs, tables, &v[b], &v[c], 7);
+}
+
+// 压缩函数F，RFC 7693 3.2
+// 计数器t和最后一块的标志在电路构建时就确定，所以 v[8..16] 都是常量
+fn compress<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: Blake2sTableIds,
+    h: &mut [UInt32<F>; 8],
+    block: &[UInt32<F>; 16],
+    bytes_compressed: u64,
+    is_last_block: bool,
+) {
+    let mut tail = BLAKE2S_IV;
+    tail[4] ^= bytes_compressed as u32;
+    tail[5] ^= (bytes_compressed >> 32) as u32;
+    if is_last_block {
+        tail[6] ^= u32::MAX;
+    }
+
+    let mut v: [UInt32<F>; 16] = std::array::from_fn(|i| {
+        if i < 8 {
+            h[i]
+        } else {
+            UInt32::allocated_constant(cs, tail[i - 8])
+        }
+    });
+
+    for sigma in BLAKE2S_SIGMA {
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [0, 4, 8, 12],
+            &block[sigma[0]],
+            &block[sigma[1]],
+        );
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [1, 5, 9, 13],
+            &block[sigma[2]],
+            &block[sigma[3]],
+        );
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [2, 6, 10, 14],
+            &block[sigma[4]],
+            &block[sigma[5]],
+        );
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [3, 7, 11, 15],
+            &block[sigma[6]],
+            &block[sigma[7]],
+        );
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [0, 5, 10, 15],
+            &block[sigma[8]],
+            &block[sigma[9]],
+        );
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [1, 6, 11, 12],
+            &block[sigma[10]],
+            &block[sigma[11]],
+        );
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [2, 7, 8, 13],
+            &block[sigma[12]],
+            &block[sigma[13]],
+        );
+        mixing_function(
+            cs,
+            tables,
+            &mut v,
+            [3, 4, 9, 14],
+            &block[sigma[14]],
+            &block[sigma[15]],
+        );
+    }
+
+    for i in 0..8 {
+        let bytes = xor_words(cs, tables, &h[i], &v[i]);
+        let t = word_from_bytes(cs, bytes);
+        let bytes = xor_words(cs, tables, &t, &v[i + 8]);
+        h[i] = word_from_bytes(cs, bytes);
+    }
+}
+
+// 无密钥、32字节输出的Blake2s-256
+fn blake2s<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: Blake2sTableIds,
+    message: &[UInt8<F>],
+) -> [UInt8<F>; BLAKE2S_OUTPUT_SIZE] {
+    // 参数块：digest长度32，key长度0，fanout = depth = 1
+    let mut initial_state = BLAKE2S_IV;
+    initial_state[0] ^= 0x01010000 ^ BLAKE2S_OUTPUT_SIZE as u32;
+    let mut h = initial_state.map(|word| UInt32::allocated_constant(cs, word));
+
+    // 最后一块用常量0补齐；空消息也要压缩一个全0的块
+    let num_blocks = std::cmp::max(
+        1,
+        (message.len() + BLAKE2S_BLOCK_SIZE - 1) / BLAKE2S_BLOCK_SIZE,
+    );
+    let zero = UInt8::allocated_constant(cs, 0);
+    let mut padded = message.to_vec();
+    padded.resize(num_blocks * BLAKE2S_BLOCK_SIZE, zero);
+
+    for (i, block) in padded.chunks_exact(BLAKE2S_BLOCK_SIZE).enumerate() {
+        let words: [UInt32<F>; 16] = std::array::from_fn(|j| {
+            let bytes: [UInt8<F>; 4] = block[4 * j..4 * j + 4].try_into().unwrap();
+            UInt32::from_le_bytes(cs, bytes)
+        });
+
+        let is_last_block = i + 1 == num_blocks;
+        let bytes_compressed = if is_last_block {
+            message.len()
+        } else {
+            (i + 1) * BLAKE2S_BLOCK_SIZE
+        };
+        compress(
+            cs,
+            tables,
+            &mut h,
+            &words,
+            bytes_compressed as u64,
+            is_last_block,
+        );
+    }
+
+    let mut digest = Vec::with_capacity(BLAKE2S_OUTPUT_SIZE);
+    for word in h {
+        digest.extend(word.to_le_bytes(cs));
+    }
+    digest.try_into().unwrap()
+}
+
+// 证明 blake2s(preimage) == claimed_digest，digest的32个字节是public input
+fn synthesize_blake2s<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: Blake2sTableIds,
+    preimage: &[u8],
+    claimed_digest: [u8; BLAKE2S_OUTPUT_SIZE],
+) {
+    let message: Vec<UInt8<F>> = preimage
+        .iter()
+        .map(|byte| UInt8::allocate_checked(cs, *byte))
+        .collect();
+
+    let digest = blake2s(cs, tables, &message);
+
+    for (computed, claimed) in digest.iter().zip(claimed_digest.iter()) {
+        let claimed = UInt8::allocate_checked(cs, *claimed);
+        let gate = PublicInputGate::new(claimed.get_variable());
+        gate.add_to_cs(cs);
+
+        Num::enforce_equal(cs, &computed.into_num(), &claimed.into_num());
+    }
+}
+
+// 设置电路参数
+const GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 60,
+    num_witness_columns: 0,
+    num_constant_columns: 8,
+    max_allowed_constraint_degree: 4,
+};
+
+const MAX_VARIABLES: usize = 1 << 20; // variable数量上限
+const MAX_TRACE_LEN: usize = 1 << 17; // 电路表格的行数上限，要能放下两张查找表（65536 + 1792行）
+
+// 配置cs的函数
+fn configure<
+    F: SmallField,
+    T: CsBuilderImpl<F, T>,
+    GC: GateConfigurationHolder<F>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    // xor8表是3列，循环移位表是4列，统一用宽度4的专用列
+    let builder = builder.allow_lookup(
+        LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+            width: 4,
+            num_repetitions: 8,
+            share_table_id: true,
+        },
+    );
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = ReductionGate::<F, 4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = BooleanConstraintGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // UInt32的模加
+    let builder = UIntXAddGate::<32>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = UIntXAddGate::<16>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        SelectionGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+    let builder = ZeroCheckGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+        false,
+    );
+    let builder = DotProductGate::<4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+fn bytes_from_hex<const N: usize>(hex: &str) -> [u8; N] {
+    std::array::from_fn(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+}
+
+// 参考实现的KAT：消息为 00 01 02 ... 的前n个字节，覆盖空消息、正好一块、跨块的情况
+const BLAKE2S_KAT: [(usize, &str); 4] = [
+    (
+        0,
+        "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9",
+    ),
+    (
+        64,
+        "56f34e8b96557e90c1f24b52d0c89d51086acf1b00f634cf1dde9233b8eaaa3e",
+    ),
+    (
+        65,
+        "1b53ee94aaf34e4b159d48de352c7f0661d0a40edff95a0b1639b4090e974472",
+    ),
+    (
+        255,
+        "f03f5789d3336b80d002d59fdf918bdb775b00956ed5528e86aa994acb38fe2d",
+    ),
+];
+
+// RFC 7693 附录B："abc"
+const RFC_7693_ABC_DIGEST: &str =
+    "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982";
+
+fn blake2s_is_satisfied(preimage: &[u8], claimed_digest: [u8; BLAKE2S_OUTPUT_SIZE]) -> bool {
+    type P = GoldilocksField;
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    let tables = add_blake2s_tables(&mut cs);
+    synthesize_blake2s(&mut cs, tables, preimage, claimed_digest);
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(8);
+    let mut cs = cs.into_assembly::<Global>();
+    cs.check_if_satisfied(&worker)
+}
+
+#[test]
+fn blake2s_rfc_7693_vectors() {
+    let digest = bytes_from_hex(RFC_7693_ABC_DIGEST);
+    assert!(blake2s_is_satisfied(b"abc", digest));
+
+    for (len, expected) in BLAKE2S_KAT {
+        let message: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let digest = bytes_from_hex(expected);
+        assert!(blake2s_is_satisfied(&message, digest), "length {}", len);
+
+        // 错误的摘要不能满足约束
+        let mut wrong_digest = digest;
+        wrong_digest[0] ^= 1;
+        assert!(blake2s_is_satisfied(&message, wrong_digest) == false);
+    }
+}
+
+#[test]
+fn blake2s_preimage_demo() {
+    type P = GoldilocksField;
+
+    let preimage = b"abc";
+    let digest: [u8; BLAKE2S_OUTPUT_SIZE] = bytes_from_hex(RFC_7693_ABC_DIGEST);
+
+    // cs builder: 约束系统的工厂类
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    let tables = add_blake2s_tables(&mut cs);
+    synthesize_blake2s(&mut cs, tables, preimage, digest);
+
+    // optional
+    cs.pad_and_shrink();
+
+    // 设置线程数量
+    let worker = Worker::new_with_num_threads(8);
+    let cs = cs.into_assembly::<Global>();
+
+    // FRI 的 LDE 因子
+    let lde_factor_to_use = 16;
+    let proof_config = ProofConfig {
+        fri_lde_factor: lde_factor_to_use,
+        pow_bits: 0,
+        merkle_tree_cap_size: 16,
+        ..Default::default()
+    };
+
+    // 使用prove_one_shot同时生成proof和vk，在生产环境不建议这样使用
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    let public_digest: Vec<GoldilocksField> = digest
+        .iter()
+        .map(|byte| GoldilocksField::from_u64_unchecked(*byte as u64))
+        .collect();
+    assert_eq!(proof.public_inputs, public_digest);
+
+    let builder_impl =
+        CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(GEOMETRY);
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
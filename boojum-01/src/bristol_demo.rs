@@ -0,0 +1,498 @@
+#![feature(allocator_api)]
+use std::alloc::Global;
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidonSponge},
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            BooleanConstraintGate, ConstantAllocatableCS, ConstantsAllocatorGate,
+            FmaGateInBaseFieldWithoutConstant, FmaGateInBaseWithoutConstantParams, NopGate,
+            PublicInputGate,
+        },
+        implementations::{
+            pow::NoPow, prover::ProofConfig, transcript::GoldilocksPoisedonTranscript,
+        },
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder, Variable,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        Field, SmallField, U64Representable,
+    },
+    worker::Worker,
+};
+
+// Bristol Fashion 格式的说明见 https://nigelsmart.github.io/MPC-Circuits/
+// 文件结构：
+//   第一行：门的数量 线的数量
+//   第二行：输入值个数 每个输入值的位宽...
+//   第三行：输出值个数 每个输出值的位宽...
+//   之后每行一个门：输入线数 输出线数 输入线... 输出线... 门类型
+// 输入线占用编号最小的那些线，输出线占用编号最大的那些线，每个值内部按低位在前排列
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BristolOp {
+    Xor,
+    And,
+    Inv,
+    // 把一根线的值复制到另一根线
+    Eqw,
+    // 给一根线赋常量值 0 或 1
+    Eq(bool),
+}
+
+#[derive(Clone, Debug)]
+struct BristolGate {
+    op: BristolOp,
+    inputs: Vec<usize>,
+    output: usize,
+}
+
+#[derive(Clone, Debug)]
+struct BristolCircuit {
+    num_wires: usize,
+    input_sizes: Vec<usize>,
+    output_sizes: Vec<usize>,
+    gates: Vec<BristolGate>,
+}
+
+impl BristolCircuit {
+    fn from_file(path: &str) -> Result<Self, String> {
+        let src = std::fs::read_to_string(path)
+            .map_err(|e| format!("不能读取电路文件 {}: {}", path, e))?;
+        Self::parse(&src)
+    }
+
+    fn parse(src: &str) -> Result<Self, String> {
+        let mut lines = src.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        fn parse_numbers(line: Option<&str>, what: &str) -> Result<Vec<usize>, String> {
+            let line = line.ok_or_else(|| format!("缺少{}", what))?;
+            line.split_whitespace()
+                .map(|t| {
+                    t.parse::<usize>()
+                        .map_err(|_| format!("{}中有非法数字: {}", what, t))
+                })
+                .collect()
+        }
+
+        let header = parse_numbers(lines.next(), "文件头")?;
+        if header.len() != 2 {
+            return Err("文件头必须是 `门数量 线数量`".to_string());
+        }
+        let (num_gates, num_wires) = (header[0], header[1]);
+
+        // 第二、三行的第一个数是值的个数，后面是每个值的位宽
+        let inputs = parse_numbers(lines.next(), "输入描述")?;
+        let outputs = parse_numbers(lines.next(), "输出描述")?;
+        if inputs.is_empty() || inputs.len() != inputs[0] + 1 {
+            return Err("输入描述的位宽个数与输入值个数不一致".to_string());
+        }
+        if outputs.is_empty() || outputs.len() != outputs[0] + 1 {
+            return Err("输出描述的位宽个数与输出值个数不一致".to_string());
+        }
+        let input_sizes = inputs[1..].to_vec();
+        let output_sizes = outputs[1..].to_vec();
+
+        let mut gates = Vec::with_capacity(num_gates);
+        for (idx, line) in lines.enumerate() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() < 3 {
+                return Err(format!("第 {} 个门格式错误: {}", idx, line));
+            }
+            let num_in: usize = tokens[0]
+                .parse()
+                .map_err(|_| format!("第 {} 个门格式错误: {}", idx, line))?;
+            let num_out: usize = tokens[1]
+                .parse()
+                .map_err(|_| format!("第 {} 个门格式错误: {}", idx, line))?;
+            if num_out != 1 || tokens.len() != 2 + num_in + num_out + 1 {
+                return Err(format!("第 {} 个门格式错误: {}", idx, line));
+            }
+            let wires = tokens[2..2 + num_in + num_out]
+                .iter()
+                .map(|t| {
+                    t.parse::<usize>()
+                        .map_err(|_| format!("第 {} 个门中有非法线编号: {}", idx, t))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(w) = wires.iter().find(|w| **w >= num_wires) {
+                return Err(format!("第 {} 个门的线编号 {} 超出范围", idx, w));
+            }
+
+            let op = match (tokens[tokens.len() - 1], num_in) {
+                ("XOR", 2) => BristolOp::Xor,
+                ("AND", 2) => BristolOp::And,
+                ("INV", 1) => BristolOp::Inv,
+                ("EQW", 1) => BristolOp::Eqw,
+                // EQ 门的“输入”是常量 0 或 1，而不是线编号
+                ("EQ", 1) if wires[0] <= 1 => BristolOp::Eq(wires[0] == 1),
+                (op, _) => return Err(format!("第 {} 个门的类型 {} 不支持", idx, op)),
+            };
+            let inputs = if let BristolOp::Eq(_) = op {
+                vec![]
+            } else {
+                wires[..num_in].to_vec()
+            };
+
+            gates.push(BristolGate {
+                op,
+                inputs,
+                output: wires[num_in],
+            });
+        }
+
+        if gates.len() != num_gates {
+            return Err(format!(
+                "文件头声明了 {} 个门，实际有 {} 个",
+                num_gates,
+                gates.len()
+            ));
+        }
+
+        let circuit = Self {
+            num_wires,
+            input_sizes,
+            output_sizes,
+            gates,
+        };
+        if circuit.num_inputs() + circuit.num_outputs() > num_wires {
+            return Err("输入输出线的数量超过了线的总数".to_string());
+        }
+
+        Ok(circuit)
+    }
+
+    fn num_inputs(&self) -> usize {
+        self.input_sizes.iter().sum()
+    }
+
+    fn num_outputs(&self) -> usize {
+        self.output_sizes.iter().sum()
+    }
+
+    // 在电路外直接计算，作为电路结果的对照
+    fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        assert_eq!(inputs.len(), self.num_inputs());
+
+        let mut wires: Vec<Option<bool>> = vec![None; self.num_wires];
+        for (wire, value) in wires.iter_mut().zip(inputs.iter()) {
+            *wire = Some(*value);
+        }
+        for gate in self.gates.iter() {
+            let get = |w: usize| wires[w].expect("门的输入线还没有被赋值");
+            let value = match gate.op {
+                BristolOp::Xor => get(gate.inputs[0]) ^ get(gate.inputs[1]),
+                BristolOp::And => get(gate.inputs[0]) & get(gate.inputs[1]),
+                BristolOp::Inv => !get(gate.inputs[0]),
+                BristolOp::Eqw => get(gate.inputs[0]),
+                BristolOp::Eq(constant) => constant,
+            };
+            wires[gate.output] = Some(value);
+        }
+
+        wires[self.num_wires - self.num_outputs()..]
+            .iter()
+            .map(|w| w.expect("输出线没有被赋值"))
+            .collect()
+    }
+
+    // 把电路放进cs，输入线是witness，输出线是public input
+    // 只有输入需要 BooleanConstraintGate，XOR/AND/INV 作用在布尔值上的结果一定还是布尔值
+    fn synthesize<F: SmallField, CS: ConstraintSystem<F>>(
+        &self,
+        cs: &mut CS,
+        inputs: &[bool],
+    ) -> Vec<Variable> {
+        assert_eq!(inputs.len(), self.num_inputs());
+
+        let one = cs.allocate_constant(F::ONE);
+        let zero = cs.allocate_constant(F::ZERO);
+        let mut minus_two = F::from_u64_unchecked(2);
+        minus_two.negate();
+
+        let mut wires: Vec<Option<Variable>> = vec![None; self.num_wires];
+        for (wire, value) in wires.iter_mut().zip(inputs.iter()) {
+            *wire = Some(BooleanConstraintGate::alloc_boolean_from_witness(
+                cs, *value,
+            ));
+        }
+
+        for gate in self.gates.iter() {
+            let get = |w: usize| wires[w].expect("门的输入线还没有被赋值");
+            let result = match gate.op {
+                BristolOp::Xor => {
+                    // a ^ b = a + b - 2ab，需要两个fma门
+                    // t = -2 * (a * b) + a
+                    let (a, b) = (get(gate.inputs[0]), get(gate.inputs[1]));
+                    let t = FmaGateInBaseFieldWithoutConstant::compute_fma(
+                        cs,
+                        minus_two,
+                        (a, b),
+                        F::ONE,
+                        a,
+                    );
+                    // c = 1 * (b * 1) + t
+                    FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (b, one), F::ONE, t)
+                }
+                BristolOp::And => {
+                    // a & b = 1 * (a * b) + 0 * 1
+                    let (a, b) = (get(gate.inputs[0]), get(gate.inputs[1]));
+                    FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (a, b), F::ZERO, one)
+                }
+                BristolOp::Inv => {
+                    // !a = -1 * (a * 1) + 1
+                    let a = get(gate.inputs[0]);
+                    FmaGateInBaseFieldWithoutConstant::compute_fma(
+                        cs,
+                        F::MINUS_ONE,
+                        (a, one),
+                        F::ONE,
+                        one,
+                    )
+                }
+                BristolOp::Eqw => get(gate.inputs[0]),
+                BristolOp::Eq(constant) => {
+                    if constant {
+                        one
+                    } else {
+                        zero
+                    }
+                }
+            };
+            wires[gate.output] = Some(result);
+        }
+
+        let outputs: Vec<Variable> = wires[self.num_wires - self.num_outputs()..]
+            .iter()
+            .map(|w| w.expect("输出线没有被赋值"))
+            .collect();
+        for output in outputs.iter() {
+            let gate = PublicInputGate::new(*output);
+            gate.add_to_cs(cs);
+        }
+
+        outputs
+    }
+}
+
+fn to_bits_le(value: u64, width: usize) -> Vec<bool> {
+    (0..width).map(|i| (value >> i) & 1 == 1).collect()
+}
+
+fn from_bits_le(bits: &[bool]) -> u64 {
+    bits.iter()
+        .enumerate()
+        .fold(0u64, |acc, (i, bit)| acc | ((*bit as u64) << i))
+}
+
+// 设置电路参数
+const GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 8,
+    num_witness_columns: 0,
+    num_constant_columns: 2,
+    max_allowed_constraint_degree: 8,
+};
+
+const MAX_VARIABLES: usize = 1 << 12; // variable数量上限
+const MAX_TRACE_LEN: usize = 1 << 10; // 电路表格的行数上限
+
+// 配置cs的函数
+fn configure<
+    F: SmallField,
+    T: CsBuilderImpl<F, T>,
+    GC: GateConfigurationHolder<F>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    // 约束输入线为布尔值
+    let builder = BooleanConstraintGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // XOR/AND/INV 都用fma门实现
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 输出线作为public input
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 在cs中加入空操作门，用于pad_and_shrink
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+const ADDER_4BIT: &str = include_str!("../bristol/adder_4bit.txt");
+const SUB_4BIT: &str = include_str!("../bristol/sub_4bit.txt");
+
+#[test]
+fn bristol_parse() {
+    let adder = BristolCircuit::parse(ADDER_4BIT).expect("不能解析adder电路");
+    assert_eq!(adder.num_wires, 22);
+    assert_eq!(adder.input_sizes, vec![4, 4]);
+    assert_eq!(adder.output_sizes, vec![4]);
+    assert_eq!(adder.gates.len(), 14);
+
+    let sub =
+        BristolCircuit::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/bristol/sub_4bit.txt"))
+            .expect("不能读取sub电路文件");
+    assert_eq!(sub.num_wires, 28);
+    assert_eq!(sub.gates.len(), 20);
+
+    // 不支持的门和错误的文件头
+    assert!(BristolCircuit::parse("1 3\n2 1 1\n1 1\n2 1 0 1 2 MAND\n").is_err());
+    assert!(BristolCircuit::parse("2 3\n2 1 1\n1 1\n2 1 0 1 2 XOR\n").is_err());
+    assert!(BristolCircuit::parse("1 3\n2 1 1\n1 1\n2 1 0 1 5 XOR\n").is_err());
+}
+
+#[test]
+fn bristol_native_evaluation() {
+    let adder = BristolCircuit::parse(ADDER_4BIT).expect("不能解析adder电路");
+    let sub = BristolCircuit::parse(SUB_4BIT).expect("不能解析sub电路");
+
+    for a in 0..16u64 {
+        for b in 0..16u64 {
+            let inputs = [to_bits_le(a, 4), to_bits_le(b, 4)].concat();
+            assert_eq!(from_bits_le(&adder.evaluate(&inputs)), (a + b) % 16);
+            assert_eq!(from_bits_le(&sub.evaluate(&inputs)), a.wrapping_sub(b) % 16);
+        }
+    }
+}
+
+#[test]
+fn bristol_circuit_matches_native() {
+    type P = GoldilocksField;
+
+    let worker = Worker::new_with_num_threads(1);
+
+    for src in [ADDER_4BIT, SUB_4BIT] {
+        let circuit = BristolCircuit::parse(src).expect("不能解析电路");
+        for (a, b) in [(0u64, 0u64), (3, 5), (9, 12), (15, 15), (7, 8)] {
+            let inputs = [to_bits_le(a, 4), to_bits_le(b, 4)].concat();
+            let expected = circuit.evaluate(&inputs);
+
+            let builder_impl =
+                CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+                    GEOMETRY,
+                    MAX_TRACE_LEN,
+                );
+            let builder = new_builder::<_, GoldilocksField>(builder_impl);
+            let builder = configure(builder);
+            let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+            let outputs = circuit.synthesize(&mut cs, &inputs);
+
+            // 约束 output * 1 = expected，电路结果必须与电路外的计算一致
+            let one = cs.allocate_constant(GoldilocksField::ONE);
+            for (output, bit) in outputs.iter().zip(expected.iter()) {
+                let expected =
+                    cs.allocate_constant(GoldilocksField::from_u64_unchecked(*bit as u64));
+                let gate = FmaGateInBaseFieldWithoutConstant {
+                    params: FmaGateInBaseWithoutConstantParams {
+                        coeff_for_quadtaric_part: GoldilocksField::ONE,
+                        linear_term_coeff: GoldilocksField::ZERO,
+                    },
+                    quadratic_part: (*output, one),
+                    linear_part: one,
+                    rhs_part: expected,
+                };
+                gate.add_to_cs(&mut cs);
+            }
+
+            cs.pad_and_shrink();
+            let mut cs = cs.into_assembly::<Global>();
+            assert!(cs.check_if_satisfied(&worker));
+        }
+    }
+}
+
+#[test]
+fn bristol_adder_demo() {
+    type P = GoldilocksField;
+
+    let adder = BristolCircuit::parse(ADDER_4BIT).expect("不能解析adder电路");
+
+    // 证明 11 + 7 = 2 (mod 16)
+    let (a, b) = (11u64, 7u64);
+    let inputs = [to_bits_le(a, 4), to_bits_le(b, 4)].concat();
+    let expected = adder.evaluate(&inputs);
+    assert_eq!(from_bits_le(&expected), (a + b) % 16);
+
+    // cs builder: 约束系统的工厂类
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    adder.synthesize(&mut cs, &inputs);
+
+    // optional
+    cs.pad_and_shrink();
+
+    // 设置线程数量
+    let worker = Worker::new_with_num_threads(1);
+    let cs = cs.into_assembly::<Global>();
+
+    // FRI 的 LDE 因子
+    let lde_factor_to_use = 16;
+    let proof_config = ProofConfig {
+        fri_lde_factor: lde_factor_to_use,
+        pow_bits: 0,
+        merkle_tree_cap_size: 1,
+        ..Default::default()
+    };
+
+    // 使用prove_one_shot同时生成proof和vk，在生产环境不建议这样使用
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    // proof中的public input就是电路外计算出的和
+    let public_bits: Vec<bool> = proof
+        .public_inputs
+        .iter()
+        .map(|x| x.as_u64_reduced() == 1)
+        .collect();
+    assert_eq!(public_bits, expected);
+
+    let builder_impl =
+        CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(GEOMETRY);
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
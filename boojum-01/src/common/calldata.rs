@@ -0,0 +1,110 @@
+// 把proof编码成以太坊calldata使用的32字节word
+//
+// - 每个Goldilocks元素占一个word：大端序，高24字节为0
+// - 扩域元素按系数c0, c1依次编码
+// - Merkle cap和路径中的每个摘要是4个元素
+// - 各部分的长度都由vk和ProofConfig决定，编码中不包含长度前缀
+//
+// 编码顺序与Proof中字段的顺序一致：
+// public_inputs, witness/stage_2/quotient的cap, final_fri_monomials,
+// values_at_z, values_at_z_omega, values_at_0, fri_base_oracle_cap, fri_intermediate_oracles_caps,
+// 每次FRI查询（witness, stage_2, quotient, setup, 每层fri：先leaf_elements再Merkle路径），
+// 最后是pow_challenge
+
+use boojum::{
+    cs::implementations::proof::{OracleQuery, Proof},
+    field::{goldilocks::GoldilocksField, ExtensionField, SmallField, U64Representable},
+};
+
+use super::{
+    demos::F,
+    recursion::{EXT, H},
+};
+
+pub type Word = [u8; 32];
+
+pub fn u64_to_word(value: u64) -> Word {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+pub fn field_to_word(el: F) -> Word {
+    u64_to_word(el.as_u64_reduced())
+}
+
+// 不是规范编码（高位不为0或者值不小于p）时返回None
+pub fn word_to_field(word: &Word) -> Option<F> {
+    if word[..24].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    let value = u64::from_be_bytes(word[24..].try_into().unwrap());
+    if value >= GoldilocksField::CHAR {
+        return None;
+    }
+
+    Some(F::from_u64_unchecked(value))
+}
+
+fn push_elements(words: &mut Vec<Word>, elements: &[F]) {
+    words.extend(elements.iter().map(|el| field_to_word(*el)));
+}
+
+fn push_ext_elements(words: &mut Vec<Word>, elements: &[ExtensionField<F, 2, EXT>]) {
+    for el in elements {
+        push_elements(words, &el.coeffs);
+    }
+}
+
+fn push_cap(words: &mut Vec<Word>, cap: &[[F; 4]]) {
+    for digest in cap {
+        push_elements(words, digest);
+    }
+}
+
+fn push_query(words: &mut Vec<Word>, query: &OracleQuery<F, H>) {
+    push_elements(words, &query.leaf_elements);
+    push_cap(words, &query.proof);
+}
+
+pub fn encode_proof(proof: &Proof<F, H, EXT>) -> Vec<Word> {
+    let mut words = vec![];
+
+    push_elements(&mut words, &proof.public_inputs);
+
+    push_cap(&mut words, &proof.witness_oracle_cap);
+    push_cap(&mut words, &proof.stage_2_oracle_cap);
+    push_cap(&mut words, &proof.quotient_oracle_cap);
+
+    for monomials in &proof.final_fri_monomials {
+        push_elements(&mut words, monomials);
+    }
+
+    push_ext_elements(&mut words, &proof.values_at_z);
+    push_ext_elements(&mut words, &proof.values_at_z_omega);
+    push_ext_elements(&mut words, &proof.values_at_0);
+
+    push_cap(&mut words, &proof.fri_base_oracle_cap);
+    for cap in &proof.fri_intermediate_oracles_caps {
+        push_cap(&mut words, cap);
+    }
+
+    for queries in &proof.queries_per_fri_repetition {
+        push_query(&mut words, &queries.witness_query);
+        push_query(&mut words, &queries.stage_2_query);
+        push_query(&mut words, &queries.quotient_query);
+        push_query(&mut words, &queries.setup_query);
+        for query in &queries.fri_queries {
+            push_query(&mut words, query);
+        }
+    }
+
+    words.push(u64_to_word(proof.pow_challenge));
+
+    words
+}
+
+// 合并成交易的calldata（不含函数选择器）
+pub fn encode_calldata(proof: &Proof<F, H, EXT>) -> Vec<u8> {
+    encode_proof(proof).concat()
+}
@@ -0,0 +1,360 @@
+// 找出电路中所有不满足的约束
+//
+// check_if_satisfied只返回bool。这里使用与它相同的数据（DevCSConfig保留了变量的放置和witness）：
+// - gates_application_sets：每一行放置的gate类型
+// - copy_permutation_data：每一列每一行放置的variable
+// - constants_requested_per_row：每一行gate使用的常量，每个实例有自己常量的gate按实例切分
+// 按gate类型在每一行重新计算约束，返回所有不为0的项
+//
+// 目前支持demo中使用的通用列上的gate（Constants, Fma, Boolean, Reduction, UIntXAdd, PublicInput, Nop），
+// 其他gate类型会列在unchecked_gates中；lookup仍然由check_if_satisfied检查
+
+use std::{any::TypeId, ops::Range, panic::Location};
+
+use boojum::{
+    config::DevCSConfig,
+    cs::{
+        gates::{
+            BooleanConstraintGate, ConstantsAllocatorGate, FmaGateInBaseFieldWithoutConstant,
+            NopGate, PublicInputGate, ReductionGate, UIntXAddGate,
+        },
+        implementations::reference_cs::CSReferenceAssembly,
+        traits::cs::ConstraintSystem,
+        Place, Variable,
+    },
+    dag::WitnessSource,
+    field::{Field, U64Representable},
+};
+
+use super::demos::F;
+
+// 一段电路的标签和放置它的位置，记录这段电路使用的行
+#[derive(Clone, Debug)]
+pub struct LabeledSection {
+    pub label: String,
+    pub location: &'static Location<'static>,
+    pub rows: Range<usize>,
+}
+
+// 在synthesize时记录每段电路新开的行
+// 同类gate会优先填满之前的行，所以一个gate也可能被算在打开这一行的那段电路中
+#[derive(Clone, Debug, Default)]
+pub struct ConstraintLabels {
+    sections: Vec<LabeledSection>,
+}
+
+impl ConstraintLabels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[track_caller]
+    pub fn section<CS: ConstraintSystem<F>, R>(
+        &mut self,
+        cs: &mut CS,
+        label: &str,
+        f: impl FnOnce(&mut CS, &mut Self) -> R,
+    ) -> R {
+        let location = Location::caller();
+        let start = cs.next_available_row();
+        let result = f(cs, self);
+        let end = cs.next_available_row();
+
+        self.sections.push(LabeledSection {
+            label: label.to_string(),
+            location,
+            rows: start..end,
+        });
+
+        result
+    }
+
+    // 包含这一行的最内层的一段
+    pub fn find(&self, row: usize) -> Option<&LabeledSection> {
+        self.sections
+            .iter()
+            .filter(|section| section.rows.contains(&row))
+            .min_by_key(|section| section.rows.len())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UnsatisfiedConstraint {
+    pub gate: &'static str,
+    pub row: usize,
+    // 同一行中的第几个gate实例，以及它占用的列
+    pub instance: usize,
+    pub columns: Range<usize>,
+    // gate的第几个约束项，以及它的值
+    pub term: usize,
+    pub value: F,
+    pub variables: Vec<(Variable, F)>,
+    pub section: Option<LabeledSection>,
+}
+
+impl std::fmt::Display for UnsatisfiedConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at row {}, instance {} (columns {:?}), term {} = {}",
+            self.gate, self.row, self.instance, self.columns, self.term, self.value
+        )?;
+        for (variable, value) in &self.variables {
+            write!(f, "\n    {:?} = {}", variable, value)?;
+        }
+        if let Some(section) = &self.section {
+            write!(
+                f,
+                "\n    placed by \"{}\" at {}",
+                section.label, section.location
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConstraintReport {
+    pub unsatisfied: Vec<UnsatisfiedConstraint>,
+    // 没有检查的gate类型和它们占用的行数
+    pub unchecked_gates: Vec<(String, usize)>,
+}
+
+impl ConstraintReport {
+    pub fn is_satisfied(&self) -> bool {
+        self.unsatisfied.is_empty()
+    }
+}
+
+// 一个gate实例的约束：输入为它的variable的值和这个实例的常量，返回每一项的值
+// 实例的最后outputs个variable是由其他variable计算出来的（用于wiring_graph）
+#[derive(Clone, Copy)]
+pub struct GateRelation {
+    pub name: &'static str,
+    pub width: usize,
+    pub outputs: usize,
+    // 每个实例自己的常量个数（例如ConstantsAllocatorGate），0表示同一行的实例共用这一行的常量
+    pub constants_per_instance: usize,
+    pub evaluate: fn(&[F], &[F]) -> Vec<F>,
+}
+
+impl GateRelation {
+    // 从这一行的常量中取出第instance个实例的常量
+    pub fn instance_constants<'a>(&self, row_constants: &'a [F], instance: usize) -> &'a [F] {
+        match self.constants_per_instance {
+            0 => row_constants,
+            per_instance => &row_constants[instance * per_instance..(instance + 1) * per_instance],
+        }
+    }
+}
+
+fn fma_terms(values: &[F], constants: &[F]) -> Vec<F> {
+    // coeff_for_quadtaric_part * a * b + linear_term_coeff * c - d
+    let mut result = constants[0];
+    result.mul_assign(&values[0]).mul_assign(&values[1]);
+    let mut linear = constants[1];
+    linear.mul_assign(&values[2]);
+    result.add_assign(&linear).sub_assign(&values[3]);
+    vec![result]
+}
+
+fn constant_terms(values: &[F], constants: &[F]) -> Vec<F> {
+    let mut result = values[0];
+    result.sub_assign(&constants[0]);
+    vec![result]
+}
+
+fn boolean_terms(values: &[F], _constants: &[F]) -> Vec<F> {
+    // x^2 - x
+    let mut result = values[0];
+    result.square().sub_assign(&values[0]);
+    vec![result]
+}
+
+fn reduction_terms<const N: usize>(values: &[F], constants: &[F]) -> Vec<F> {
+    // sum c_i * x_i - result
+    let mut result = F::ZERO;
+    for (value, coeff) in values[..N].iter().zip(constants) {
+        let mut term = *coeff;
+        term.mul_assign(value);
+        result.add_assign(&term);
+    }
+    result.sub_assign(&values[N]);
+    vec![result]
+}
+
+fn uint_add_terms<const WIDTH: usize>(values: &[F], _constants: &[F]) -> Vec<F> {
+    // a + b + carry_in - c - 2^WIDTH * carry_out，并且carry_out是布尔值
+    let [a, b, carry_in, c, carry_out] = values.try_into().unwrap();
+    let mut sum = a;
+    sum.add_assign(&b).add_assign(&carry_in).sub_assign(&c);
+    let mut shifted = F::from_u64_unchecked(1u64 << WIDTH);
+    shifted.mul_assign(&carry_out);
+    sum.sub_assign(&shifted);
+
+    vec![sum, boolean_terms(&[carry_out], &[])[0]]
+}
+
+fn no_terms(_values: &[F], _constants: &[F]) -> Vec<F> {
+    vec![]
+}
+
+pub fn gate_relation(type_id: TypeId) -> Option<GateRelation> {
+    let relations = [
+        (
+            TypeId::of::<FmaGateInBaseFieldWithoutConstant<F>>(),
+            GateRelation {
+                name: "FmaGateInBaseFieldWithoutConstant",
+                width: 4,
+                outputs: 1,
+                constants_per_instance: 0,
+                evaluate: fma_terms,
+            },
+        ),
+        (
+            TypeId::of::<ConstantsAllocatorGate<F>>(),
+            GateRelation {
+                name: "ConstantsAllocatorGate",
+                width: 1,
+                outputs: 1,
+                constants_per_instance: 1,
+                evaluate: constant_terms,
+            },
+        ),
+        (
+            TypeId::of::<BooleanConstraintGate>(),
+            GateRelation {
+                name: "BooleanConstraintGate",
+                width: 1,
+                outputs: 0,
+                constants_per_instance: 0,
+                evaluate: boolean_terms,
+            },
+        ),
+        (
+            TypeId::of::<ReductionGate<F, 3>>(),
+            GateRelation {
+                name: "ReductionGate<3>",
+                width: 4,
+                outputs: 1,
+                constants_per_instance: 0,
+                evaluate: reduction_terms::<3>,
+            },
+        ),
+        (
+            TypeId::of::<ReductionGate<F, 4>>(),
+            GateRelation {
+                name: "ReductionGate<4>",
+                width: 5,
+                outputs: 1,
+                constants_per_instance: 0,
+                evaluate: reduction_terms::<4>,
+            },
+        ),
+        (
+            TypeId::of::<UIntXAddGate<8>>(),
+            GateRelation {
+                name: "UIntXAddGate<8>",
+                width: 5,
+                outputs: 2,
+                constants_per_instance: 0,
+                evaluate: uint_add_terms::<8>,
+            },
+        ),
+        (
+            TypeId::of::<PublicInputGate>(),
+            GateRelation {
+                name: "PublicInputGate",
+                width: 1,
+                outputs: 0,
+                constants_per_instance: 0,
+                evaluate: no_terms,
+            },
+        ),
+        (
+            TypeId::of::<NopGate>(),
+            GateRelation {
+                name: "NopGate",
+                width: 1,
+                outputs: 0,
+                constants_per_instance: 0,
+                evaluate: no_terms,
+            },
+        ),
+    ];
+
+    relations
+        .into_iter()
+        .find(|(id, _)| *id == type_id)
+        .map(|(_, relation)| relation)
+}
+
+pub fn find_unsatisfied_constraints(
+    cs: &CSReferenceAssembly<F, F, DevCSConfig>,
+    labels: Option<&ConstraintLabels>,
+) -> ConstraintReport {
+    let num_columns = cs.parameters.num_columns_under_copy_permutation;
+    let evaluation_data = &cs.evaluation_data_over_general_purpose_columns;
+
+    let mut report = ConstraintReport::default();
+    for (row, gate_idx) in cs.gates_application_sets.iter().enumerate() {
+        let type_id = evaluation_data.gate_type_ids_for_general_purpose_columns[*gate_idx];
+        let Some(relation) = gate_relation(type_id) else {
+            let name = evaluation_data.evaluators_over_general_purpose_columns[*gate_idx]
+                .debug_name
+                .clone();
+            match report
+                .unchecked_gates
+                .iter_mut()
+                .find(|(gate, _)| *gate == name)
+            {
+                Some((_, rows)) => *rows += 1,
+                None => report.unchecked_gates.push((name, 1)),
+            }
+            continue;
+        };
+
+        for instance in 0..num_columns / relation.width {
+            let columns = instance * relation.width..(instance + 1) * relation.width;
+            let variables: Vec<Variable> = columns
+                .clone()
+                .map(|column| cs.copy_permutation_data[column][row])
+                .collect();
+            // 这一行没有填满时剩下的位置是placeholder
+            if variables.iter().any(|variable| variable.is_placeholder()) {
+                continue;
+            }
+
+            let values: Vec<F> = variables
+                .iter()
+                .map(|variable| cs.get_value_unchecked(Place::from_variable(*variable)))
+                .collect();
+            let constants =
+                relation.instance_constants(&cs.constants_requested_per_row[row], instance);
+            for (term, value) in (relation.evaluate)(&values, constants)
+                .into_iter()
+                .enumerate()
+            {
+                if value.is_zero() {
+                    continue;
+                }
+                report.unsatisfied.push(UnsatisfiedConstraint {
+                    gate: relation.name,
+                    row,
+                    instance,
+                    columns: columns.clone(),
+                    term,
+                    value,
+                    variables: variables
+                        .iter()
+                        .copied()
+                        .zip(values.iter().copied())
+                        .collect(),
+                    section: labels.and_then(|labels| labels.find(row)).cloned(),
+                });
+            }
+        }
+    }
+
+    report
+}
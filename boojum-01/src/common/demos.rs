@@ -0,0 +1,677 @@
+// 把六个基础demo的电路整理成统一的接口，方便harness用不同的配置生成和验证证明
+// 电路与各自文件中的测试保持一致
+
+use boojum::{
+    cs::{
+        cs_builder::{CsBuilder, CsBuilderImpl},
+        gates::{
+            BooleanConstraintGate, ConstantAllocatableCS, ConstantsAllocatorGate,
+            FmaGateInBaseFieldWithoutConstant, FmaGateInBaseWithoutConstantParams, NopGate,
+            PublicInputGate, ReductionGate, ReductionGateParams, UIntXAddGate,
+        },
+        implementations::{lookup_table::LookupTable, prover::ProofConfig},
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, LookupParameters, StaticToolboxHolder, Variable,
+    },
+    field::{goldilocks::GoldilocksField, Field, U64Representable},
+    gadgets::{traits::witnessable::CSWitnessable, u8::UInt8},
+};
+use derivative::Derivative;
+
+pub type F = GoldilocksField;
+
+// demo的证明参数（harness中的ProofProfile::Demo），verifier_api只接受这个配置的proof
+pub fn demo_proof_config() -> ProofConfig {
+    ProofConfig {
+        fri_lde_factor: 16,
+        pow_bits: 0,
+        merkle_tree_cap_size: 4,
+        ..Default::default()
+    }
+}
+
+pub trait DemoCircuit {
+    fn name(&self) -> &'static str;
+
+    fn geometry(&self) -> CSGeometry;
+
+    // variable数量上限
+    fn max_variables(&self) -> usize;
+
+    // 电路表格的行数上限
+    fn max_trace_len(&self) -> usize;
+
+    // configure中使用的lookup参数
+    fn lookup_parameters(&self) -> LookupParameters {
+        LookupParameters::NoLookup
+    }
+
+    // 配置cs的函数，prover和verifier必须使用相同的配置
+    fn configure<T: CsBuilderImpl<F, T>, GC: GateConfigurationHolder<F>, TB: StaticToolboxHolder>(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder>;
+
+    // 构建电路并填入witness
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS);
+
+    // 证明中应当出现的public input
+    fn public_inputs(&self) -> Vec<F>;
+}
+
+pub fn fibonacci(n: usize) -> u64 {
+    let (mut a, mut b) = (1u64, 1u64);
+    for _ in 0..n - 2 {
+        (a, b) = (b, a + b);
+    }
+    b
+}
+
+// 斐波那契数列第n项（从1, 1开始）前n - 2步的计算过程
+fn synthesize_fibonacci<CS: ConstraintSystem<F>>(cs: &mut CS, n: usize, one: Variable) -> Variable {
+    assert!(n > 2, "n must be greater than 2");
+
+    let mut a = cs.alloc_single_variable_from_witness(F::ONE);
+    let mut b = cs.alloc_single_variable_from_witness(F::ONE);
+    for _ in 0..n - 2 {
+        let c = FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (a, one), F::ONE, b);
+        a = b;
+        b = c;
+    }
+    b
+}
+
+fn enforce_equal_with_one<CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    a: Variable,
+    one: Variable,
+    b: Variable,
+) {
+    // a * 1 + 0 * 1 = b
+    let gate = FmaGateInBaseFieldWithoutConstant {
+        params: FmaGateInBaseWithoutConstantParams {
+            coeff_for_quadtaric_part: F::ONE,
+            linear_term_coeff: F::ZERO,
+        },
+        quadratic_part: (a, one),
+        linear_part: one,
+        rhs_part: b,
+    };
+    gate.add_to_cs(cs);
+}
+
+// simple_fibonacci.rs：结果作为public input
+#[derive(Clone, Debug)]
+pub struct FibonacciDemo {
+    pub n: usize,
+    pub out: u64,
+}
+
+impl FibonacciDemo {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            out: fibonacci(n),
+        }
+    }
+}
+
+impl Default for FibonacciDemo {
+    fn default() -> Self {
+        Self::new(9)
+    }
+}
+
+impl DemoCircuit for FibonacciDemo {
+    fn name(&self) -> &'static str {
+        "simple_fibonacci"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        CSGeometry {
+            num_columns_under_copy_permutation: 8,
+            num_witness_columns: 0,
+            num_constant_columns: 2,
+            max_allowed_constraint_degree: 8,
+        }
+    }
+
+    fn max_variables(&self) -> usize {
+        512
+    }
+
+    fn max_trace_len(&self) -> usize {
+        128
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = PublicInputGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let one = cs.allocate_constant(F::ONE);
+        let c = synthesize_fibonacci(cs, self.n, one);
+
+        let out = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.out));
+        let gate = PublicInputGate::new(out);
+        gate.add_to_cs(cs);
+
+        enforce_equal_with_one(cs, c, one, out);
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![F::from_u64_unchecked(self.out)]
+    }
+}
+
+// prove_verify_fibonacci.rs：结果作为常量写进电路，没有public input
+#[derive(Clone, Debug)]
+pub struct ConstantFibonacciDemo {
+    pub n: usize,
+    pub out: u64,
+}
+
+impl ConstantFibonacciDemo {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            out: fibonacci(n),
+        }
+    }
+}
+
+impl Default for ConstantFibonacciDemo {
+    fn default() -> Self {
+        Self::new(9)
+    }
+}
+
+impl DemoCircuit for ConstantFibonacciDemo {
+    fn name(&self) -> &'static str {
+        "prove_verify_fibonacci"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        CSGeometry {
+            num_columns_under_copy_permutation: 8,
+            num_witness_columns: 0,
+            num_constant_columns: 2,
+            max_allowed_constraint_degree: 8,
+        }
+    }
+
+    fn max_variables(&self) -> usize {
+        512
+    }
+
+    fn max_trace_len(&self) -> usize {
+        128
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let one = cs.allocate_constant(F::ONE);
+        let c = synthesize_fibonacci(cs, self.n, one);
+
+        let out = cs.allocate_constant(F::from_u64_unchecked(self.out));
+        enforce_equal_with_one(cs, c, one, out);
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![]
+    }
+}
+
+// simple_poly.rs：x^3 + x + 5 = out，out是public input
+#[derive(Clone, Debug)]
+pub struct SimplePolyDemo {
+    pub x: u64,
+    pub out: u64,
+}
+
+impl Default for SimplePolyDemo {
+    fn default() -> Self {
+        Self { x: 3, out: 35 }
+    }
+}
+
+impl DemoCircuit for SimplePolyDemo {
+    fn name(&self) -> &'static str {
+        "simple_poly"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        CSGeometry {
+            num_columns_under_copy_permutation: 8,
+            num_witness_columns: 0,
+            num_constant_columns: 3,
+            max_allowed_constraint_degree: 8,
+        }
+    }
+
+    fn max_variables(&self) -> usize {
+        512
+    }
+
+    fn max_trace_len(&self) -> usize {
+        128
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = PublicInputGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = ReductionGate::<F, 3>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let one = cs.allocate_constant(F::ONE);
+        let five = cs.allocate_constant(F::from_u64_unchecked(5));
+
+        let out = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.out));
+        let gate = PublicInputGate::new(out);
+        gate.add_to_cs(cs);
+
+        let x = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.x));
+        let x_square =
+            FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (x, x), F::ZERO, one);
+        let x_cube =
+            FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (x_square, x), F::ZERO, one);
+
+        let gate = ReductionGate {
+            params: ReductionGateParams {
+                reduction_constants: [F::ONE; 3],
+            },
+            terms: [x_cube, x, five],
+            reduction_result: out,
+        };
+        gate.add_to_cs(cs);
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![F::from_u64_unchecked(self.out)]
+    }
+}
+
+// boolean_demo.rs：布尔变量b满足 b = 1
+#[derive(Clone, Debug)]
+pub struct BooleanDemo {
+    pub value: bool,
+}
+
+impl Default for BooleanDemo {
+    fn default() -> Self {
+        Self { value: true }
+    }
+}
+
+impl DemoCircuit for BooleanDemo {
+    fn name(&self) -> &'static str {
+        "boolean_demo"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        CSGeometry {
+            num_columns_under_copy_permutation: 16,
+            num_witness_columns: 0,
+            num_constant_columns: 2,
+            max_allowed_constraint_degree: 5,
+        }
+    }
+
+    fn max_variables(&self) -> usize {
+        32
+    }
+
+    fn max_trace_len(&self) -> usize {
+        16
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = BooleanConstraintGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let b = BooleanConstraintGate::alloc_boolean_from_witness(cs, self.value);
+        let one = cs.alloc_single_variable_from_witness(F::ONE);
+
+        // 0 * b * b + 1 * b = one
+        let gate = FmaGateInBaseFieldWithoutConstant {
+            params: FmaGateInBaseWithoutConstantParams {
+                coeff_for_quadtaric_part: F::ZERO,
+                linear_term_coeff: F::ONE,
+            },
+            quadratic_part: (b, b),
+            linear_part: b,
+            rhs_part: one,
+        };
+        gate.add_to_cs(cs);
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![]
+    }
+}
+
+// uint_demo.rs：x + (x + x) + (x - x) + x * x = expected
+#[derive(Clone, Debug)]
+pub struct Uint8Demo {
+    pub x: u8,
+    pub expected: u64,
+}
+
+impl Default for Uint8Demo {
+    fn default() -> Self {
+        Self { x: 1, expected: 4 }
+    }
+}
+
+impl DemoCircuit for Uint8Demo {
+    fn name(&self) -> &'static str {
+        "uint8_demo"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        CSGeometry {
+            num_columns_under_copy_permutation: 16,
+            num_witness_columns: 0,
+            num_constant_columns: 4,
+            max_allowed_constraint_degree: 5,
+        }
+    }
+
+    fn max_variables(&self) -> usize {
+        32
+    }
+
+    fn max_trace_len(&self) -> usize {
+        16
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = ReductionGate::<_, 4>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = UIntXAddGate::<8>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let x = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.x as u64));
+        let x = unsafe { UInt8::<F>::from_variable_unchecked(x) };
+
+        let result1 = x;
+        let result2 = x.add_no_overflow(cs, x);
+        let result3 = x.sub_no_overflow(cs, x);
+        let result4 = x.into_num().mul(cs, &x.into_num());
+        let result4 = unsafe { UInt8::<F>::from_variable_unchecked(result4.as_variables_set()[0]) };
+
+        let expected = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.expected));
+        let gate = ReductionGate {
+            params: ReductionGateParams {
+                reduction_constants: [F::ONE; 4],
+            },
+            terms: [
+                result1.as_variables_set()[0],
+                result2.as_variables_set()[0],
+                result3.as_variables_set()[0],
+                result4.as_variables_set()[0],
+            ],
+            reduction_result: expected,
+        };
+        gate.add_to_cs(cs);
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![]
+    }
+}
+
+pub const DEMO_TABLE_NAME: &str = "Test table";
+
+// 设置一个空的结构体，用来标识这个lookup table
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug)]
+pub struct DemoTableMarker;
+
+// 与lookup_demo.rs相同的3比特 xor / or / and 表
+pub fn create_demo_table() -> LookupTable<F, 5> {
+    let mut all_keys = Vec::with_capacity(64);
+    for a in 0..8 {
+        for b in 0..8 {
+            let key = smallvec::smallvec![F::from_u64_unchecked(a), F::from_u64_unchecked(b)];
+            all_keys.push(key);
+        }
+    }
+    LookupTable::new_from_keys_and_generation_function(
+        &all_keys,
+        DEMO_TABLE_NAME.to_string(),
+        2,
+        |keys| {
+            let a = keys[0].as_u64_reduced();
+            let b = keys[1].as_u64_reduced();
+
+            smallvec::smallvec![
+                F::from_u64_unchecked(a ^ b),
+                F::from_u64_unchecked(a | b),
+                F::from_u64_unchecked(a & b)
+            ]
+        },
+    )
+}
+
+// lookup_demo.rs：重复检查 1 xor 2 = 3
+#[derive(Clone, Debug)]
+pub struct LookupDemo {
+    pub a: u64,
+    pub b: u64,
+    // 声称的a xor b
+    pub expected: u64,
+    pub repetitions: usize,
+}
+
+impl LookupDemo {
+    pub fn new(a: u64, b: u64, repetitions: usize) -> Self {
+        Self {
+            a,
+            b,
+            expected: a ^ b,
+            repetitions,
+        }
+    }
+}
+
+impl Default for LookupDemo {
+    fn default() -> Self {
+        Self::new(1, 2, 101)
+    }
+}
+
+const LOOKUP_DEMO_PARAMETERS: LookupParameters =
+    LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+        width: 5,
+        num_repetitions: 2,
+        share_table_id: true,
+    };
+
+impl DemoCircuit for LookupDemo {
+    fn name(&self) -> &'static str {
+        "lookup_demo"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        CSGeometry {
+            num_columns_under_copy_permutation: 8,
+            num_witness_columns: 0,
+            num_constant_columns: 2,
+            max_allowed_constraint_degree: 8,
+        }
+    }
+
+    fn max_variables(&self) -> usize {
+        1 << 16
+    }
+
+    fn max_trace_len(&self) -> usize {
+        128
+    }
+
+    fn lookup_parameters(&self) -> LookupParameters {
+        LOOKUP_DEMO_PARAMETERS
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = builder.allow_lookup(LOOKUP_DEMO_PARAMETERS);
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let table_id = cs.add_lookup_table::<DemoTableMarker, 5>(create_demo_table());
+
+        let one = cs.allocate_constant(F::ONE);
+        let expected = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.expected));
+
+        for _ in 0..self.repetitions {
+            let a = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.a));
+            let b = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(self.b));
+
+            let [xor, _or, _and] = cs.perform_lookup::<2, 3>(table_id, &[a, b]);
+            enforce_equal_with_one(cs, xor, one, expected);
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![]
+    }
+}
+
+// DemoCircuit有泛型方法，不能做成trait object，所以用visitor依次访问每个demo
+pub trait DemoVisitor {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D);
+}
+
+pub fn visit_all_demos<V: DemoVisitor>(visitor: &mut V) {
+    visitor.visit(&FibonacciDemo::default());
+    visitor.visit(&ConstantFibonacciDemo::default());
+    visitor.visit(&SimplePolyDemo::default());
+    visitor.visit(&BooleanDemo::default());
+    visitor.visit(&Uint8Demo::default());
+    visitor.visit(&LookupDemo::default());
+}
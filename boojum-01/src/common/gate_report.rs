@@ -0,0 +1,213 @@
+// 每个电路的gate使用情况和selector树
+//
+// - 每个配置的gate：放置方式、使用的行数、每行的实例数、约束次数
+// - boojum根据gate的次数生成的selector树，以及加上selector之后的约束次数和quotient的次数
+// - 警告：配置了但没有使用的gate；占用了大部分行、放到specialized列更便宜的gate
+
+use std::fmt::Write;
+
+use boojum::{
+    config::DevCSConfig,
+    cs::implementations::{reference_cs::CSReferenceAssembly, setup::TreeNode},
+    worker::Worker,
+};
+
+use super::{demos::F, security::quotient_degree};
+
+#[derive(Clone, Debug)]
+pub struct GateUsage {
+    pub name: String,
+    pub specialized: bool,
+    pub rows: usize,
+    pub instances_per_row: usize,
+    pub instance_width: usize,
+    pub degree: usize,
+    pub num_constants: usize,
+    // 在selector树中的路径，0为左，1为右；specialized列中的gate没有selector
+    pub selector_path: Option<Vec<bool>>,
+}
+
+impl GateUsage {
+    // 加上selector之后的次数
+    pub fn degree_with_selectors(&self) -> usize {
+        self.degree + self.selector_path.as_ref().map_or(0, |path| path.len())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GateReport {
+    pub trace_len: usize,
+    pub gates: Vec<GateUsage>,
+    pub selector_tree: String,
+    pub max_degree: usize,
+    pub quotient_degree: usize,
+    pub warnings: Vec<String>,
+}
+
+fn selector_path(node: &TreeNode, gate_idx: usize) -> Option<Vec<bool>> {
+    match node {
+        TreeNode::Empty => None,
+        TreeNode::GateOnly(description) => (description.gate_idx == gate_idx).then(Vec::new),
+        TreeNode::Fork { left, right } => {
+            if let Some(mut path) = selector_path(left, gate_idx) {
+                path.insert(0, false);
+                Some(path)
+            } else if let Some(mut path) = selector_path(right, gate_idx) {
+                path.insert(0, true);
+                Some(path)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// 每个叶子一行：缩进表示深度，前面是selector路径
+fn format_tree(out: &mut String, node: &TreeNode, gate_names: &[String], path: &str) {
+    let indent = "  ".repeat(path.len());
+    match node {
+        TreeNode::Empty => writeln!(out, "{}[{}] (empty)", indent, path).unwrap(),
+        TreeNode::GateOnly(description) => writeln!(
+            out,
+            "{}[{}] {}",
+            indent, path, gate_names[description.gate_idx]
+        )
+        .unwrap(),
+        TreeNode::Fork { left, right } => {
+            format_tree(out, left, gate_names, &format!("{}0", path));
+            format_tree(out, right, gate_names, &format!("{}1", path));
+        }
+    }
+}
+
+pub fn gate_report(cs: &CSReferenceAssembly<F, F, DevCSConfig>, worker: &Worker) -> GateReport {
+    let trace_len = cs.gates_application_sets.len();
+    let (_, selectors_placement, _) = cs.create_constant_setup_polys(worker);
+
+    let general_purpose = &cs.evaluation_data_over_general_purpose_columns;
+    let gate_names: Vec<String> = general_purpose
+        .evaluators_over_general_purpose_columns
+        .iter()
+        .map(|evaluator| evaluator.debug_name.clone())
+        .collect();
+
+    let mut gates: Vec<GateUsage> = general_purpose
+        .evaluators_over_general_purpose_columns
+        .iter()
+        .enumerate()
+        .map(|(gate_idx, evaluator)| GateUsage {
+            name: evaluator.debug_name.clone(),
+            specialized: false,
+            rows: cs
+                .gates_application_sets
+                .iter()
+                .filter(|gate| **gate == gate_idx)
+                .count(),
+            instances_per_row: evaluator.num_repetitions_on_row,
+            instance_width: evaluator.instance_width,
+            degree: evaluator.max_constraint_degree,
+            num_constants: evaluator.num_required_constants,
+            selector_path: selector_path(&selectors_placement, gate_idx),
+        })
+        .collect();
+
+    // specialized列中的gate在每一行都有，不需要selector
+    gates.extend(
+        cs.evaluation_data_over_specialized_columns
+            .evaluators_over_specialized_columns
+            .iter()
+            .map(|evaluator| GateUsage {
+                name: evaluator.debug_name.clone(),
+                specialized: true,
+                rows: trace_len,
+                instances_per_row: evaluator.num_repetitions_on_row,
+                instance_width: evaluator.instance_width,
+                degree: evaluator.max_constraint_degree,
+                num_constants: evaluator.num_required_constants,
+                selector_path: None,
+            }),
+    );
+
+    let mut selector_tree = String::new();
+    format_tree(&mut selector_tree, &selectors_placement, &gate_names, "");
+
+    let max_degree = gates
+        .iter()
+        .map(|gate| gate.degree_with_selectors())
+        .max()
+        .unwrap_or(1);
+    let quotient_degree = quotient_degree(max_degree);
+
+    let mut warnings = vec![];
+    for gate in &gates {
+        if gate.rows == 0 {
+            warnings.push(format!(
+                "{} is configured but never used, it still takes a place in the selector tree",
+                gate.name
+            ));
+        }
+        // 超过一半的行都是这个gate：放到specialized列可以去掉它的selector，通用列也能留给其他gate
+        if !gate.specialized && gate.rows * 2 > trace_len && gates.len() > 1 {
+            warnings.push(format!(
+                "{} uses {} of {} rows, consider GatePlacementStrategy::UseSpecializedColumns",
+                gate.name, gate.rows, trace_len
+            ));
+        }
+    }
+
+    GateReport {
+        trace_len,
+        gates,
+        selector_tree,
+        max_degree,
+        quotient_degree,
+        warnings,
+    }
+}
+
+impl std::fmt::Display for GateReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<48} {:<11} {:>6} {:>8} {:>6} {:>6} {:>9}  selector",
+            "gate", "placement", "rows", "per row", "width", "degree", "constants"
+        )?;
+        for gate in &self.gates {
+            let path = match &gate.selector_path {
+                Some(path) => path
+                    .iter()
+                    .map(|bit| if *bit { '1' } else { '0' })
+                    .collect(),
+                None => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{:<48} {:<11} {:>6} {:>8} {:>6} {:>6} {:>9}  {}",
+                gate.name,
+                if gate.specialized {
+                    "specialized"
+                } else {
+                    "general"
+                },
+                gate.rows,
+                gate.instances_per_row,
+                gate.instance_width,
+                gate.degree,
+                gate.num_constants,
+                path
+            )?;
+        }
+        writeln!(f, "trace length {}", self.trace_len)?;
+        writeln!(f, "selector tree:")?;
+        write!(f, "{}", self.selector_tree)?;
+        writeln!(
+            f,
+            "max degree with selectors {}, quotient degree {}",
+            self.max_degree, self.quotient_degree
+        )?;
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
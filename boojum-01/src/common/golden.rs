@@ -0,0 +1,221 @@
+// 固定的proof和vk，用来发现proof格式和transcript的变化
+//
+// - 确定性证明：只用一个线程，所有计算按固定顺序进行；NoPow，所以不依赖grinding的结果
+// - 每个demo的vk和proof以JSON保存在测试源文件旁边的fixtures/golden下（本仓库中是src/fixtures/golden），
+//   复制到其他crate的tests目录时fixture跟着测试一起移动
+// - 检查：新的代码仍然能验证旧的proof，相同的电路生成完全相同的vk（以及proof）
+// - vk不同时逐个字段列出差异
+//
+// 设置BOOJUM_UPDATE_GOLDEN=1时生成（或覆盖）fixture；没有设置时fixture不存在是错误，
+// 否则新的checkout或者CI上检查总是通过
+
+use std::path::{Path, PathBuf};
+
+use boojum::{
+    cs::implementations::{pow::NoPow, proof::Proof, verifier::VerificationKey},
+    worker::Worker,
+};
+use serde_json::Value;
+
+use super::{
+    demos::{DemoCircuit, F},
+    harness::{prove, verify, ProofProfile},
+    recursion::{EXT, H, TR},
+};
+
+pub const GOLDEN_NUM_THREADS: usize = 1;
+
+pub fn golden_dir() -> PathBuf {
+    // file!()是相对于workspace根目录的路径，不一定是CARGO_MANIFEST_DIR，所以向上查找
+    let source = Path::new(file!());
+    let source = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .ancestors()
+        .map(|dir| dir.join(source))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| source.to_path_buf());
+
+    // common/golden.rs -> 测试源文件所在的目录
+    source
+        .parent()
+        .and_then(Path::parent)
+        .expect("golden.rs应当在测试目录的common下")
+        .join("fixtures")
+        .join("golden")
+}
+
+pub fn update_golden() -> bool {
+    std::env::var("BOOJUM_UPDATE_GOLDEN").is_ok()
+}
+
+pub fn prove_deterministic<D: DemoCircuit>(demo: &D) -> (Proof<F, H, EXT>, VerificationKey<F, H>) {
+    let worker = Worker::new_with_num_threads(GOLDEN_NUM_THREADS);
+    prove::<D, TR, H, NoPow>(demo, &worker, ProofProfile::Demo.proof_config())
+}
+
+pub fn to_bytes<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = serde_json::to_vec_pretty(value).expect("不能序列化");
+    bytes.push(b'\n');
+    bytes
+}
+
+// 每个不同的字段一行：路径: 旧值 -> 新值
+pub fn json_diff(old: &Value, new: &Value) -> Vec<String> {
+    let mut lines = vec![];
+    diff_at("", old, new, &mut lines);
+    lines
+}
+
+fn diff_at(path: &str, old: &Value, new: &Value, lines: &mut Vec<String>) {
+    let path_or_root = if path.is_empty() { "." } else { path };
+    match (old, new) {
+        (Value::Object(old_fields), Value::Object(new_fields)) => {
+            for (key, old_value) in old_fields {
+                let field_path = format!("{}.{}", path, key);
+                match new_fields.get(key) {
+                    Some(new_value) => diff_at(&field_path, old_value, new_value, lines),
+                    None => lines.push(format!("{}: removed", field_path)),
+                }
+            }
+            for key in new_fields
+                .keys()
+                .filter(|key| !old_fields.contains_key(*key))
+            {
+                lines.push(format!("{}.{}: added", path, key));
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            // 叶子数组（例如摘要）整体显示
+            let is_leaf = |items: &Vec<Value>| {
+                items
+                    .iter()
+                    .all(|item| !item.is_array() && !item.is_object())
+            };
+            if old_items.len() != new_items.len() {
+                lines.push(format!(
+                    "{}: length {} -> {}",
+                    path_or_root,
+                    old_items.len(),
+                    new_items.len()
+                ));
+            }
+            if is_leaf(old_items) && is_leaf(new_items) {
+                if old_items.len() == new_items.len() && old_items != new_items {
+                    lines.push(format!("{}: {} -> {}", path_or_root, old, new));
+                }
+                return;
+            }
+            for (index, (old_item, new_item)) in old_items.iter().zip(new_items).enumerate() {
+                diff_at(&format!("{}[{}]", path, index), old_item, new_item, lines);
+            }
+        }
+        _ => {
+            if old != new {
+                lines.push(format!("{}: {} -> {}", path_or_root, old, new));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GoldenCheck {
+    pub demo: &'static str,
+    // 设置了BOOJUM_UPDATE_GOLDEN时写入了新的文件
+    pub written: bool,
+    pub old_proof_verifies: bool,
+    pub vk_diff: Vec<String>,
+    pub proof_diff: Vec<String>,
+}
+
+impl GoldenCheck {
+    pub fn is_unchanged(&self) -> bool {
+        self.old_proof_verifies && self.vk_diff.is_empty() && self.proof_diff.is_empty()
+    }
+}
+
+impl std::fmt::Display for GoldenCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.written {
+            return writeln!(f, "{}: wrote new fixtures", self.demo);
+        }
+        writeln!(
+            f,
+            "{}: old proof {}",
+            self.demo,
+            if self.old_proof_verifies {
+                "verifies"
+            } else {
+                "DOES NOT verify"
+            }
+        )?;
+        if !self.vk_diff.is_empty() {
+            writeln!(f, "vk changed:")?;
+            for line in &self.vk_diff {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        if !self.proof_diff.is_empty() {
+            writeln!(f, "proof changed in {} places:", self.proof_diff.len())?;
+            for line in self.proof_diff.iter().take(20) {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn check_golden<D: DemoCircuit>(demo: &D) -> GoldenCheck {
+    let vk_path = golden_dir().join(format!("{}.vk.json", demo.name()));
+    let proof_path = golden_dir().join(format!("{}.proof.json", demo.name()));
+    let (proof, vk) = prove_deterministic(demo);
+    let (vk_bytes, proof_bytes) = (to_bytes(&vk), to_bytes(&proof));
+
+    if update_golden() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        std::fs::write(&vk_path, &vk_bytes).unwrap();
+        std::fs::write(&proof_path, &proof_bytes).unwrap();
+        return GoldenCheck {
+            demo: demo.name(),
+            written: true,
+            old_proof_verifies: true,
+            ..Default::default()
+        };
+    }
+
+    let read_fixture = |path: &PathBuf| {
+        std::fs::read(path).unwrap_or_else(|err| {
+            panic!(
+                "不能读取fixture {}: {}；用BOOJUM_UPDATE_GOLDEN=1生成并提交",
+                path.display(),
+                err
+            )
+        })
+    };
+    let old_vk_bytes = read_fixture(&vk_path);
+    let old_proof_bytes = read_fixture(&proof_path);
+    let old_vk: VerificationKey<F, H> = serde_json::from_slice(&old_vk_bytes).unwrap();
+    let old_proof: Proof<F, H, EXT> = serde_json::from_slice(&old_proof_bytes).unwrap();
+
+    let diff = |old: &[u8], new: &[u8]| {
+        if old == new {
+            return vec![];
+        }
+        let lines = json_diff(
+            &serde_json::from_slice(old).unwrap(),
+            &serde_json::from_slice(new).unwrap(),
+        );
+        // 值相同但格式不同
+        if lines.is_empty() {
+            vec!["serialized bytes differ".to_string()]
+        } else {
+            lines
+        }
+    };
+
+    GoldenCheck {
+        demo: demo.name(),
+        written: false,
+        old_proof_verifies: verify::<D, TR, H, NoPow>(demo, &old_vk, &old_proof),
+        vk_diff: diff(&old_vk_bytes, &vk_bytes),
+        proof_diff: diff(&old_proof_bytes, &proof_bytes),
+    }
+}
@@ -0,0 +1,377 @@
+// 用统一的流程为demo生成和验证证明
+// transcript和Merkle树的哈希可以在Poseidon和Blake2s之间切换，PoW也可以选择不同的实现
+
+use std::{alloc::Global, time::Duration, time::Instant};
+
+use blake2::Blake2s256;
+use boojum::{
+    algebraic_props::{
+        round_function::AbsorptionModeOverwrite,
+        sponge::{GoldilocksPoseidon2Sponge, GoldilocksPoseidonSponge},
+    },
+    config::DevCSConfig,
+    cs::{
+        cs_builder::new_builder,
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        implementations::{
+            pow::{NoPow, PoWRunner},
+            proof::Proof,
+            prover::ProofConfig,
+            reference_cs::CSReferenceAssembly,
+            transcript::{Blake2sTranscript, GoldilocksPoisedonTranscript, Transcript},
+            verifier::{VerificationKey, Verifier},
+        },
+        oracle::TreeHasher,
+        traits::GoodAllocator,
+    },
+    dag::CircuitResolverOpts,
+    field::goldilocks::GoldilocksExt2,
+    worker::Worker,
+};
+use tracing::info_span;
+
+use super::{
+    demos::{demo_proof_config, DemoCircuit, F},
+    security::check_production_security,
+};
+
+// Fiat-Shamir transcript与Merkle树哈希的组合
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashConfiguration {
+    // GoldilocksPoisedonTranscript + GoldilocksPoseidonSponge，适合递归验证
+    Poseidon,
+    // Blake2sTranscript + Blake2s256，与外层使用Blake2s承诺的系统一致
+    Blake2s,
+}
+
+// 生成proof时grinding使用的哈希，难度由ProofConfig中的pow_bits决定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowConfiguration {
+    // 不做grinding，pow_bits必须为0
+    None,
+    Blake2s,
+    // Poseidon2 sponge
+    Poseidon,
+}
+
+// 常用的证明参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofProfile {
+    // 与demo中相同：不做grinding
+    Demo,
+    // 通过grinding把每次FRI查询的安全性提高pow_bits比特
+    Production,
+    // 属性测试使用：更小的lde因子，证明更快
+    Fast,
+}
+
+impl ProofProfile {
+    pub fn proof_config(&self) -> ProofConfig {
+        match self {
+            ProofProfile::Demo => demo_proof_config(),
+            ProofProfile::Production => ProofConfig {
+                fri_lde_factor: 8,
+                pow_bits: 20,
+                merkle_tree_cap_size: 16,
+                ..Default::default()
+            },
+            ProofProfile::Fast => ProofConfig {
+                fri_lde_factor: 8,
+                pow_bits: 0,
+                merkle_tree_cap_size: 4,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProveVerifyReport {
+    pub demo: &'static str,
+    pub hash: HashConfiguration,
+    pub pow: PowConfiguration,
+    pub pow_bits: u32,
+    // proof序列化为JSON之后的字节数
+    pub proof_size: usize,
+    pub prove_time: Duration,
+    pub verify_time: Duration,
+    pub is_valid: bool,
+}
+
+// 构建电路并转换为prover使用的assembly
+pub fn synthesize_assembly<D: DemoCircuit>(demo: &D) -> CSReferenceAssembly<F, F, DevCSConfig> {
+    synthesize_assembly_in::<D, Global>(demo)
+}
+
+// prover中的多项式使用分配器A，例如用profiling::TrackingAllocator统计内存
+pub fn synthesize_assembly_in<D: DemoCircuit, A: GoodAllocator>(
+    demo: &D,
+) -> CSReferenceAssembly<F, F, DevCSConfig, A> {
+    // witness在分配variable时就被计算出来，所以这一段也包括witness的计算
+    let mut cs = info_span!("synthesize", demo = demo.name()).in_scope(|| {
+        let builder_impl = CsReferenceImplementationBuilder::<F, F, DevCSConfig>::new(
+            demo.geometry(),
+            demo.max_trace_len(),
+        );
+        let builder = new_builder::<_, F>(builder_impl);
+        let builder = D::configure(builder);
+        let mut cs = builder.build(CircuitResolverOpts::new(demo.max_variables()));
+
+        demo.synthesize(&mut cs);
+        cs
+    });
+
+    info_span!("into_assembly").in_scope(|| {
+        cs.pad_and_shrink();
+        cs.into_assembly::<A>()
+    })
+}
+
+pub fn prove<D, TR, H, POW>(
+    demo: &D,
+    worker: &Worker,
+    proof_config: ProofConfig,
+) -> (Proof<F, H, GoldilocksExt2>, VerificationKey<F, H>)
+where
+    D: DemoCircuit,
+    TR: Transcript<F, TransciptParameters = ()>,
+    H: TreeHasher<F, Output = TR::CompatibleCap>,
+    POW: PoWRunner,
+{
+    prove_in::<D, TR, H, POW, Global>(demo, worker, proof_config)
+}
+
+// 与prove_one_shot相同，同时生成proof和vk，在生产环境不建议这样使用
+// setup和证明分开执行，分别记录在"setup"和"prove"两段中
+pub fn prove_in<D, TR, H, POW, A>(
+    demo: &D,
+    worker: &Worker,
+    proof_config: ProofConfig,
+) -> (Proof<F, H, GoldilocksExt2>, VerificationKey<F, H>)
+where
+    D: DemoCircuit,
+    TR: Transcript<F, TransciptParameters = ()>,
+    H: TreeHasher<F, Output = TR::CompatibleCap>,
+    POW: PoWRunner,
+    A: GoodAllocator,
+{
+    let cs = synthesize_assembly_in::<D, A>(demo);
+
+    let (setup_base, setup, vk, setup_tree, vars_hint, witness_hints) = info_span!("setup")
+        .in_scope(|| {
+            cs.get_full_setup::<H>(
+                worker,
+                proof_config.fri_lde_factor,
+                proof_config.merkle_tree_cap_size,
+            )
+        });
+
+    // witness的LDE、copy permutation和lookup的累积乘积、quotient和FRI都在prove_from_precomputations中
+    let proof = info_span!("prove").in_scope(|| {
+        cs.prove_from_precomputations::<GoldilocksExt2, TR, H, POW>(
+            proof_config,
+            &setup_base,
+            &setup,
+            &setup_tree,
+            &vk,
+            &vars_hint,
+            &witness_hints,
+            (),
+            worker,
+        )
+    });
+
+    (proof, vk)
+}
+
+pub fn verify<D, TR, H, POW>(
+    demo: &D,
+    vk: &VerificationKey<F, H>,
+    proof: &Proof<F, H, GoldilocksExt2>,
+) -> bool
+where
+    D: DemoCircuit,
+    TR: Transcript<F, TransciptParameters = ()>,
+    H: TreeHasher<F, Output = TR::CompatibleCap>,
+    POW: PoWRunner,
+{
+    let builder_impl = CsVerifierBuilder::<F, GoldilocksExt2>::new_from_parameters(demo.geometry());
+    let builder = new_builder::<_, F>(builder_impl);
+    let builder = D::configure(builder);
+    let verifier = builder.build(());
+
+    // PoW的nonce（proof.pow_challenge）也在这里检查
+    info_span!("verify").in_scope(|| verifier.verify::<H, TR, POW>((), vk, proof))
+}
+
+// 为一个vk构建一次verifier，之后可以重复使用，也可以在Worker的多个线程上并行验证
+pub struct PreparedVerifier<H: TreeHasher<F>> {
+    verifier: Verifier<F, GoldilocksExt2>,
+    vk: VerificationKey<F, H>,
+}
+
+impl<H: TreeHasher<F>> PreparedVerifier<H> {
+    pub fn new<D: DemoCircuit>(demo: &D, vk: VerificationKey<F, H>) -> Self {
+        let builder_impl =
+            CsVerifierBuilder::<F, GoldilocksExt2>::new_from_parameters(demo.geometry());
+        let builder = new_builder::<_, F>(builder_impl);
+        let builder = D::configure(builder);
+        let verifier = builder.build(());
+
+        Self { verifier, vk }
+    }
+
+    pub fn verify<TR, POW>(&self, proof: &Proof<F, H, GoldilocksExt2>) -> bool
+    where
+        TR: Transcript<F, TransciptParameters = ()>,
+        H: TreeHasher<F, Output = TR::CompatibleCap>,
+        POW: PoWRunner,
+    {
+        self.verifier.verify::<H, TR, POW>((), &self.vk, proof)
+    }
+
+    // 返回每个proof的验证结果，顺序与proofs相同
+    pub fn verify_batch<TR, POW>(
+        &self,
+        proofs: &[Proof<F, H, GoldilocksExt2>],
+        worker: &Worker,
+    ) -> Vec<bool>
+    where
+        TR: Transcript<F, TransciptParameters = ()>,
+        H: TreeHasher<F, Output = TR::CompatibleCap>,
+        POW: PoWRunner,
+    {
+        let mut results = vec![false; proofs.len()];
+        if proofs.is_empty() {
+            return results;
+        }
+
+        worker.scope(proofs.len(), |scope, chunk_size| {
+            for (proofs, results) in proofs
+                .chunks(chunk_size)
+                .zip(results.chunks_mut(chunk_size))
+            {
+                scope.spawn(move |_| {
+                    for (proof, result) in proofs.iter().zip(results.iter_mut()) {
+                        *result = self.verify::<TR, POW>(proof);
+                    }
+                });
+            }
+        });
+
+        results
+    }
+}
+
+fn prove_and_verify_with<D, TR, H, POW>(
+    demo: &D,
+    hash: HashConfiguration,
+    pow: PowConfiguration,
+    worker: &Worker,
+    proof_config: ProofConfig,
+) -> ProveVerifyReport
+where
+    D: DemoCircuit,
+    TR: Transcript<F, TransciptParameters = ()>,
+    H: TreeHasher<F, Output = TR::CompatibleCap>,
+    POW: PoWRunner,
+{
+    let pow_bits = proof_config.pow_bits;
+
+    let start = Instant::now();
+    let (proof, vk) = prove::<D, TR, H, POW>(demo, worker, proof_config);
+    let prove_time = start.elapsed();
+
+    let proof_size = serde_json::to_vec(&proof).expect("不能序列化proof").len();
+
+    let start = Instant::now();
+    let is_valid =
+        verify::<D, TR, H, POW>(demo, &vk, &proof) && proof.public_inputs == demo.public_inputs();
+    let verify_time = start.elapsed();
+
+    ProveVerifyReport {
+        demo: demo.name(),
+        hash,
+        pow,
+        pow_bits,
+        proof_size,
+        prove_time,
+        verify_time,
+        is_valid,
+    }
+}
+
+fn prove_and_verify_with_hash<D, TR, H>(
+    demo: &D,
+    hash: HashConfiguration,
+    pow: PowConfiguration,
+    worker: &Worker,
+    proof_config: ProofConfig,
+) -> ProveVerifyReport
+where
+    D: DemoCircuit,
+    TR: Transcript<F, TransciptParameters = ()>,
+    H: TreeHasher<F, Output = TR::CompatibleCap>,
+{
+    match pow {
+        PowConfiguration::None => {
+            assert_eq!(proof_config.pow_bits, 0, "NoPow不能用于pow_bits > 0");
+            prove_and_verify_with::<D, TR, H, NoPow>(demo, hash, pow, worker, proof_config)
+        }
+        PowConfiguration::Blake2s => {
+            prove_and_verify_with::<D, TR, H, Blake2s256>(demo, hash, pow, worker, proof_config)
+        }
+        PowConfiguration::Poseidon => prove_and_verify_with::<
+            D,
+            TR,
+            H,
+            GoldilocksPoseidon2Sponge<AbsorptionModeOverwrite>,
+        >(demo, hash, pow, worker, proof_config),
+    }
+}
+
+pub fn prove_and_verify<D: DemoCircuit>(
+    demo: &D,
+    hash: HashConfiguration,
+    pow: PowConfiguration,
+    worker: &Worker,
+    proof_config: ProofConfig,
+) -> ProveVerifyReport {
+    match hash {
+        HashConfiguration::Poseidon => prove_and_verify_with_hash::<
+            D,
+            GoldilocksPoisedonTranscript,
+            GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        >(demo, hash, pow, worker, proof_config),
+        HashConfiguration::Blake2s => {
+            prove_and_verify_with_hash::<D, Blake2sTranscript, Blake2s256>(
+                demo,
+                hash,
+                pow,
+                worker,
+                proof_config,
+            )
+        }
+    }
+}
+
+// 生成"production" proof之前先估计安全性，低于min_bits时拒绝生成
+// trace长度取电路的上限max_trace_len，得到的是偏保守的估计
+pub fn prove_and_verify_production<D: DemoCircuit>(
+    demo: &D,
+    hash: HashConfiguration,
+    pow: PowConfiguration,
+    worker: &Worker,
+    proof_config: ProofConfig,
+    min_bits: f64,
+) -> Result<ProveVerifyReport, String> {
+    check_production_security(
+        &proof_config,
+        &demo.geometry(),
+        demo.max_trace_len(),
+        min_bits,
+    )?;
+
+    Ok(prove_and_verify(demo, hash, pow, worker, proof_config))
+}
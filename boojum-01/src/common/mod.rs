@@ -0,0 +1,18 @@
+// 多个demo共用的电路和证明流程
+// src下每个文件都是独立编译的，不是每个文件都会用到这里所有的函数
+#![allow(dead_code)]
+
+pub mod calldata;
+pub mod debugger;
+pub mod demos;
+pub mod gate_report;
+pub mod golden;
+pub mod harness;
+pub mod profiling;
+pub mod recursion;
+pub mod security;
+pub mod tampering;
+pub mod trace;
+pub mod underconstrained;
+pub mod verifier_api;
+pub mod wiring_graph;
@@ -0,0 +1,288 @@
+// 记录harness中每个阶段的时间、内存和Worker的利用率
+//
+// - harness用tracing的span标记阶段：synthesize（包括witness的计算）、into_assembly、setup、prove、verify。
+//   prover内部的阶段（LDE/FFT、copy permutation和lookup的累积乘积、quotient、FRI）不单独记录：
+//   它们都在boojum的prove_from_precomputations中，不修改boojum不能从外面分开，都算在"prove"中
+// - 内存：TrackingAllocator包装Global，作为assembly的分配器（见harness::prove_in），
+//   统计prover中多项式等使用的内存；其他使用Global的分配不在统计中
+// - Worker的利用率：进程的CPU时间 / (墙上时间 * 线程数)，只在unix上可用
+// - 内存计数和CPU时间都是整个进程的，所以profile()的调用互斥执行，测试并行运行时也不会互相干扰
+// - 结果可以导出为Chrome trace JSON（chrome://tracing或者Perfetto）
+
+use std::{
+    alloc::{AllocError, Allocator, Global, Layout},
+    cell::Cell,
+    collections::HashMap,
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use boojum::cs::traits::GoodAllocator;
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer, Registry};
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+// 同一时间只有一个profile()在记录
+static PROFILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrackingAllocator;
+
+unsafe impl Allocator for TrackingAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = Global.allocate(layout)?;
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+impl GoodAllocator for TrackingAllocator {}
+
+pub fn allocated_bytes() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+pub fn peak_bytes() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+#[cfg(unix)]
+fn process_cpu_time() -> Option<Duration> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    let to_duration = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    };
+
+    Some(to_duration(usage.ru_utime) + to_duration(usage.ru_stime))
+}
+
+#[cfg(not(unix))]
+fn process_cpu_time() -> Option<Duration> {
+    None
+}
+
+thread_local! {
+    static THREAD_ID: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(1);
+
+// Chrome trace中使用的线程编号
+fn thread_id() -> u64 {
+    THREAD_ID.with(|id| match id.get() {
+        Some(id) => id,
+        None => {
+            let new_id = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed) as u64;
+            id.set(Some(new_id));
+            new_id
+        }
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct StageRecord {
+    pub name: &'static str,
+    pub depth: usize,
+    pub thread: u64,
+    // 相对于开始记录的时间
+    pub start: Duration,
+    pub wall_time: Duration,
+    pub cpu_time: Option<Duration>,
+    // 这一段中TrackingAllocator的最大使用量，以及结束时的使用量
+    pub peak_bytes: usize,
+    pub allocated_bytes: usize,
+}
+
+impl StageRecord {
+    pub fn utilization(&self, num_threads: usize) -> Option<f64> {
+        let cpu_time = self.cpu_time?;
+        let available = self.wall_time.as_secs_f64() * num_threads as f64;
+        (available > 0.0).then(|| cpu_time.as_secs_f64() / available)
+    }
+}
+
+struct OpenStage {
+    name: &'static str,
+    depth: usize,
+    start: Instant,
+    cpu_start: Option<Duration>,
+    // 外层的最大使用量，这一段结束后恢复
+    outer_peak: usize,
+}
+
+struct ProfileState {
+    start: Instant,
+    open: HashMap<span::Id, OpenStage>,
+    depth: usize,
+    stages: Vec<StageRecord>,
+}
+
+pub struct ProfilingLayer {
+    state: Arc<Mutex<ProfileState>>,
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for ProfilingLayer {
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        let depth = state.depth;
+        state.depth += 1;
+
+        // 从这里开始重新统计最大使用量
+        let outer_peak = PEAK.swap(ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+        state.open.insert(
+            id.clone(),
+            OpenStage {
+                name: span.name(),
+                depth,
+                start: Instant::now(),
+                cpu_start: process_cpu_time(),
+                outer_peak,
+            },
+        );
+    }
+
+    fn on_exit(&self, id: &span::Id, _ctx: Context<'_, S>) {
+        let end = Instant::now();
+        let cpu_end = process_cpu_time();
+        let mut state = self.state.lock().unwrap();
+        let Some(stage) = state.open.remove(id) else {
+            return;
+        };
+        state.depth -= 1;
+
+        let peak = PEAK.fetch_max(stage.outer_peak, Ordering::Relaxed);
+        let record = StageRecord {
+            name: stage.name,
+            depth: stage.depth,
+            thread: thread_id(),
+            start: stage.start - state.start,
+            wall_time: end - stage.start,
+            cpu_time: cpu_end.zip(stage.cpu_start).map(|(end, start)| end - start),
+            peak_bytes: peak,
+            allocated_bytes: ALLOCATED.load(Ordering::Relaxed),
+        };
+        state.stages.push(record);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProfileReport {
+    pub num_threads: usize,
+    // 按开始的时间排序
+    pub stages: Vec<StageRecord>,
+}
+
+// 在f中记录所有的阶段；num_threads为Worker的线程数，用来计算利用率
+pub fn profile<R>(num_threads: usize, f: impl FnOnce() -> R) -> (R, ProfileReport) {
+    // 之前的调用panic时锁被poison，但计数仍然可以使用
+    let _guard = PROFILE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    PEAK.store(ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+
+    let state = Arc::new(Mutex::new(ProfileState {
+        start: Instant::now(),
+        open: HashMap::new(),
+        depth: 0,
+        stages: vec![],
+    }));
+    let subscriber = Registry::default().with(ProfilingLayer {
+        state: state.clone(),
+    });
+    let result = tracing::subscriber::with_default(subscriber, f);
+
+    let mut stages = std::mem::take(&mut state.lock().unwrap().stages);
+    stages.sort_by_key(|stage| stage.start);
+
+    (
+        result,
+        ProfileReport {
+            num_threads,
+            stages,
+        },
+    )
+}
+
+impl ProfileReport {
+    pub fn stage(&self, name: &str) -> Option<&StageRecord> {
+        self.stages.iter().find(|stage| stage.name == name)
+    }
+
+    pub fn to_chrome_trace(&self) -> serde_json::Value {
+        let events: Vec<_> = self
+            .stages
+            .iter()
+            .map(|stage| {
+                serde_json::json!({
+                    "name": stage.name,
+                    "cat": "prover",
+                    "ph": "X",
+                    "ts": stage.start.as_micros() as u64,
+                    "dur": stage.wall_time.as_micros() as u64,
+                    "pid": 1,
+                    "tid": stage.thread,
+                    "args": {
+                        "peak_bytes": stage.peak_bytes,
+                        "allocated_bytes": stage.allocated_bytes,
+                        "cpu_time_us": stage.cpu_time.map(|time| time.as_micros() as u64),
+                        "utilization": stage.utilization(self.num_threads),
+                    },
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms",
+        })
+    }
+}
+
+impl std::fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>12} {:>14} {:>12}",
+            "stage", "wall ms", "cpu ms", "peak bytes", "utilization"
+        )?;
+        for stage in &self.stages {
+            let name = format!("{}{}", "  ".repeat(stage.depth), stage.name);
+            let cpu_time = match stage.cpu_time {
+                Some(time) => format!("{:.3}", time.as_secs_f64() * 1000.0),
+                None => "-".to_string(),
+            };
+            let utilization = match stage.utilization(self.num_threads) {
+                Some(utilization) => format!("{:.1}%", utilization * 100.0),
+                None => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{:<24} {:>12.3} {:>12} {:>14} {:>12}",
+                name,
+                stage.wall_time.as_secs_f64() * 1000.0,
+                cpu_time,
+                stage.peak_bytes,
+                utilization
+            )?;
+        }
+        writeln!(f, "worker threads: {}", self.num_threads)
+    }
+}
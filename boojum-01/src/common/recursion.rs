@@ -0,0 +1,158 @@
+// 在电路中验证demo的proof
+//
+// boojum的递归验证器包含了验证所需的全部部分：
+// - 扩域GoldilocksExt2上的运算（FmaGateInExtensionWithoutConstant）
+// - 基于Poseidon sponge的电路内transcript，重新生成所有挑战
+// - Merkle cap和Merkle路径的检查
+// - 每次FRI查询的折叠和一致性检查
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidonSponge},
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        gates::{
+            BooleanConstraintGate, ConstantsAllocatorGate, FmaGateInBaseFieldWithoutConstant,
+            FmaGateInExtensionWithoutConstant, NopGate, ParallelSelectionGate, PublicInputGate,
+            ReductionGate, SelectionGate, ZeroCheckGate,
+        },
+        implementations::{
+            pow::NoPow, proof::Proof, transcript::GoldilocksPoisedonTranscript,
+            verifier::VerificationKey,
+        },
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    field::goldilocks::GoldilocksExt2,
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        recursion::{
+            allocated_proof::AllocatedProof, allocated_vk::AllocatedVerificationKey,
+            recursive_transcript::CircuitAlgebraicSpongeBasedTranscript,
+            recursive_tree_hasher::CircuitGoldilocksPoseidonSponge,
+            recursive_verifier_builder::CsRecursiveVerifierBuilder,
+        },
+        traits::round_function::BuildableCircuitRoundFunction,
+    },
+    implementations::poseidon_goldilocks::PoseidonGoldilocks,
+};
+
+use super::demos::{DemoCircuit, F};
+
+pub type EXT = GoldilocksExt2;
+
+// 被验证的proof使用的transcript和Merkle树哈希（与demo相同）
+pub type TR = GoldilocksPoisedonTranscript;
+pub type H = GoldilocksPoseidonSponge<AbsorptionModeOverwrite>;
+
+// 它们在电路中的对应实现
+pub type RH = CircuitGoldilocksPoseidonSponge;
+pub type RTR = CircuitAlgebraicSpongeBasedTranscript<F, 8, 12, 4, PoseidonGoldilocks>;
+
+// 递归电路的参数：Poseidon置换和扩域运算都比较宽，需要更多的列
+pub const RECURSION_GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 100,
+    num_witness_columns: 0,
+    num_constant_columns: 8,
+    max_allowed_constraint_degree: 8,
+};
+
+pub const RECURSION_MAX_VARIABLES: usize = 1 << 22; // variable数量上限
+pub const RECURSION_MAX_TRACE_LEN: usize = 1 << 18; // 电路表格的行数上限
+
+// 配置递归电路cs的函数
+pub fn configure_recursion<
+    T: CsBuilderImpl<F, T>,
+    GC: GateConfigurationHolder<F>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = BooleanConstraintGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // Poseidon置换所需的门由round function自己配置
+    let builder =
+        <PoseidonGoldilocks as BuildableCircuitRoundFunction<F, 8, 12, 4>>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+    let builder = ZeroCheckGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+        false,
+    );
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 扩域上的乘加
+    let builder = FmaGateInExtensionWithoutConstant::<F, EXT>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 把查询位置拆成比特
+    let builder = ReductionGate::<F, 4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        SelectionGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+    // Merkle路径中按比特交换左右节点
+    let builder = ParallelSelectionGate::<4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+// 在电路中验证一个由D的配置生成的proof，要求proof有效，返回它的public input
+// vk作为常量写进电路，所以电路只接受这一个vk的proof
+pub fn verify_in_circuit<D: DemoCircuit, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    inner_geometry: CSGeometry,
+    vk: &VerificationKey<F, H>,
+    proof: &Proof<F, H, EXT>,
+) -> Vec<Num<F>> {
+    // 用内层电路的配置构建电路内的verifier
+    let builder_impl =
+        CsRecursiveVerifierBuilder::<'_, F, EXT, _>::new_from_parameters(cs, inner_geometry);
+    let builder = new_builder::<_, F>(builder_impl);
+    let builder = D::configure(builder);
+    let verifier = builder.build(());
+
+    let allocated_vk = AllocatedVerificationKey::<F, RH>::allocate_constant(cs, vk.clone());
+    let allocated_proof = AllocatedProof::<F, RH, EXT>::allocate_from_witness(
+        cs,
+        Some(proof.clone()),
+        &verifier,
+        &vk.fixed_parameters,
+        &proof.proof_config,
+    );
+
+    let (is_valid, public_inputs) = verifier.verify::<RH, TR, RTR, NoPow>(
+        cs,
+        (),
+        &allocated_proof,
+        &vk.fixed_parameters,
+        &proof.proof_config,
+        &allocated_vk,
+    );
+
+    let boolean_true = Boolean::allocated_constant(cs, true);
+    Boolean::enforce_equal(cs, &is_valid, &boolean_true);
+
+    public_inputs
+}
@@ -0,0 +1,172 @@
+// 估计一组证明参数的安全比特数
+//
+// 模型（简化）：
+// - FRI查询次数与boojum的计算方式相同：ceil((security_level - pow_bits) / log2(lde_factor))
+// - conjectured：每次查询贡献 log2(1/rate) 比特，再加上grinding的pow_bits；
+//   同时受扩域大小限制，DEEP/OOD检查的错误概率约为 degree * trace_len / |F_ext|，
+//   FRI折叠的错误概率约为 |D| / |F_ext|
+// - proven：Johnson界（参考ethSTARK），每次查询只贡献约 log2(1/rate) / 2 比特，
+//   折叠阶段的错误概率为 (m + 1/2)^7 * |D|^2 / (3 * rate^1.5 * |F_ext|)，取 m = 3
+// merkle_tree_cap_size只影响proof大小，不影响安全性
+//
+// 低于最低安全要求时拒绝生成proof的入口是harness::prove_and_verify_production；
+// 这些示例只有测试，没有可执行文件，所以没有命令行的入口
+
+use boojum::cs::{implementations::prover::ProofConfig, CSGeometry};
+
+// GoldilocksExt2：|F_ext| = p^2 ≈ 2^128
+pub const EXTENSION_FIELD_BITS: f64 = 128.0;
+
+// Johnson界中的近似参数
+const JOHNSON_PROXIMITY_PARAMETER: f64 = 3.0;
+
+// 生成"production" proof时要求的最低安全比特数
+pub const MIN_PRODUCTION_SECURITY_BITS: f64 = 100.0;
+
+// grinding超过这个难度时，单个proof的grinding时间已经不可忽略
+const MAX_SUGGESTED_POW_BITS: u32 = 24;
+
+#[derive(Clone, Debug)]
+pub struct SecurityEstimate {
+    pub num_queries: usize,
+    pub rate_bits: f64,
+    // 所有FRI查询加上PoW
+    pub conjectured_query_bits: f64,
+    pub proven_query_bits: f64,
+    // 受扩域大小限制的部分
+    pub conjectured_field_bits: f64,
+    pub proven_field_bits: f64,
+    pub conjectured_bits: f64,
+    pub proven_bits: f64,
+}
+
+pub fn num_fri_queries(proof_config: &ProofConfig) -> usize {
+    let rate_bits = proof_config.fri_lde_factor.trailing_zeros() as usize;
+    assert!(rate_bits > 0, "fri_lde_factor必须是大于1的2的幂");
+
+    let bits_from_queries = proof_config
+        .security_level
+        .saturating_sub(proof_config.pow_bits as usize);
+    (bits_from_queries + rate_bits - 1) / rate_bits
+}
+
+pub fn estimate_security(
+    proof_config: &ProofConfig,
+    geometry: &CSGeometry,
+    trace_len: usize,
+) -> SecurityEstimate {
+    assert!(proof_config.fri_lde_factor.is_power_of_two());
+    assert!(trace_len.is_power_of_two());
+
+    let num_queries = num_fri_queries(proof_config);
+    let rate_bits = (proof_config.fri_lde_factor as f64).log2();
+    let pow_bits = proof_config.pow_bits as f64;
+
+    let trace_bits = (trace_len as f64).log2();
+    let domain_bits = trace_bits + rate_bits;
+    let degree_bits = (geometry.max_allowed_constraint_degree as f64).log2();
+
+    // conjectured
+    let conjectured_query_bits = num_queries as f64 * rate_bits + pow_bits;
+    let deep_bits = EXTENSION_FIELD_BITS - degree_bits - trace_bits;
+    let folding_bits = EXTENSION_FIELD_BITS - domain_bits;
+    let conjectured_field_bits = deep_bits.min(folding_bits);
+
+    // proven
+    let m = JOHNSON_PROXIMITY_PARAMETER;
+    let sqrt_rate = (-rate_bits / 2.0).exp2();
+    let per_query_bits = -(sqrt_rate * (1.0 + 1.0 / (2.0 * m))).log2();
+    let proven_query_bits = num_queries as f64 * per_query_bits + pow_bits;
+    let johnson_folding_bits = EXTENSION_FIELD_BITS
+        - (7.0 * (m + 0.5).log2() + 2.0 * domain_bits + 1.5 * rate_bits - 3f64.log2());
+    let proven_field_bits = deep_bits.min(johnson_folding_bits);
+
+    SecurityEstimate {
+        num_queries,
+        rate_bits,
+        conjectured_query_bits,
+        proven_query_bits,
+        conjectured_field_bits,
+        proven_field_bits,
+        conjectured_bits: conjectured_query_bits.min(conjectured_field_bits),
+        proven_bits: proven_query_bits.min(proven_field_bits),
+    }
+}
+
+// quotient的次数（相对于trace的长度）是约束次数减1，向上取2的幂
+pub fn quotient_degree(max_constraint_degree: usize) -> usize {
+    (max_constraint_degree.max(2) - 1).next_power_of_two()
+}
+
+// prover要求lde因子不小于quotient的次数
+pub fn min_fri_lde_factor(geometry: &CSGeometry) -> usize {
+    quotient_degree(geometry.max_allowed_constraint_degree)
+}
+
+// 在满足conjectured安全目标的配置中选最便宜的：
+// prover的开销主要由LDE的大小决定，所以先比较lde因子，再比较查询次数（proof大小），最后比较grinding
+pub fn cheapest_config(
+    geometry: &CSGeometry,
+    trace_len: usize,
+    target_bits: usize,
+    merkle_tree_cap_size: usize,
+) -> Option<(ProofConfig, SecurityEstimate)> {
+    let mut best: Option<(ProofConfig, SecurityEstimate)> = None;
+
+    let min_rate_bits = min_fri_lde_factor(geometry).trailing_zeros().max(1);
+    for rate_bits in min_rate_bits..=5u32 {
+        for pow_bits in 0..=MAX_SUGGESTED_POW_BITS {
+            let proof_config = ProofConfig {
+                fri_lde_factor: 1 << rate_bits,
+                merkle_tree_cap_size,
+                pow_bits,
+                security_level: target_bits,
+                ..Default::default()
+            };
+            let estimate = estimate_security(&proof_config, geometry, trace_len);
+            if estimate.conjectured_bits < target_bits as f64 {
+                continue;
+            }
+
+            let cost = (rate_bits, estimate.num_queries, pow_bits);
+            let is_cheaper = match &best {
+                Some((config, best_estimate)) => {
+                    cost < (
+                        config.fri_lde_factor.trailing_zeros(),
+                        best_estimate.num_queries,
+                        config.pow_bits,
+                    )
+                }
+                None => true,
+            };
+            if is_cheaper {
+                best = Some((proof_config, estimate));
+            }
+        }
+    }
+
+    best
+}
+
+// "production" proof必须达到最低的安全比特数
+pub fn check_production_security(
+    proof_config: &ProofConfig,
+    geometry: &CSGeometry,
+    trace_len: usize,
+    min_bits: f64,
+) -> Result<SecurityEstimate, String> {
+    let estimate = estimate_security(proof_config, geometry, trace_len);
+    if estimate.conjectured_bits < min_bits {
+        return Err(format!(
+            "conjectured security {:.1} bits is below the required {:.1} bits \
+             (lde factor {}, {} queries, pow bits {})",
+            estimate.conjectured_bits,
+            min_bits,
+            proof_config.fri_lde_factor,
+            estimate.num_queries,
+            proof_config.pow_bits
+        ));
+    }
+
+    Ok(estimate)
+}
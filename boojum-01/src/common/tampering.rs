@@ -0,0 +1,533 @@
+// 修改合法的proof和vk，检查verifier拒绝每一种修改
+//
+// 修改的方式：
+// - proof中每一部分的域元素加1（较长的部分只取第一个、中间和最后一个）
+// - 交换Merkle cap中的两个摘要
+// - 修改、增加、删除public input
+// - 删除一次FRI查询、一次查询中的最后一层、Merkle路径中的最后一个摘要
+// - 修改PoW的nonce：pow_bits为0时verifier不检查nonce，所以这里生成proof时使用Blake2s grinding
+// - proof中的proof_config：降低security_level并删除相应的查询，用降低的pow_bits跳过grinding，修改lde因子和cap大小
+// - vk中setup的Merkle cap
+// 验证时先用verifier_api::check_proof检查参数和形状（与wasm的verifier相同），再调用verifier。
+// 两处的拒绝分开统计：只被check_proof拒绝的修改，直接调用boojum的verifier时可能被接受。
+// verifier panic说明缺少检查，与接受一样是失败
+
+use std::{
+    collections::BTreeMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use blake2::Blake2s256;
+use boojum::{
+    cs::implementations::{
+        proof::{OracleQuery, Proof, SingleRoundQueries},
+        prover::ProofConfig,
+        verifier::VerificationKey,
+    },
+    field::{ExtensionField, Field},
+    worker::Worker,
+};
+
+use super::{
+    demos::{DemoCircuit, F},
+    harness::{prove, verify, PreparedVerifier, ProofProfile},
+    recursion::{EXT, H, TR},
+    security::num_fri_queries,
+    verifier_api::check_proof,
+};
+
+type POW = Blake2s256;
+
+// 修改后的nonce碰巧满足grinding的概率是2^-TAMPERING_POW_BITS，16比特时可以忽略，grinding也不影响测试时间
+pub const TAMPERING_POW_BITS: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    // 被verifier_api::check_proof拒绝，没有调用verifier
+    RejectedByCheck,
+    // 通过了check_proof，被boojum的verifier拒绝
+    RejectedByVerifier,
+    Panicked,
+    Accepted,
+}
+
+impl Outcome {
+    pub fn is_rejected(&self) -> bool {
+        matches!(self, Outcome::RejectedByCheck | Outcome::RejectedByVerifier)
+    }
+}
+
+// 一个字段的修改次数，以及在哪里被拒绝
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub total: usize,
+    pub by_check: usize,
+    pub by_verifier: usize,
+}
+
+impl Coverage {
+    pub fn rejected(&self) -> usize {
+        self.by_check + self.by_verifier
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Tampered {
+    // proof或vk中被修改的字段，用于统计覆盖情况
+    pub field: String,
+    pub mutation: String,
+    pub proof: Proof<F, H, EXT>,
+    // 只有修改vk时才有
+    pub vk: Option<VerificationKey<F, H>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TamperResult {
+    pub field: String,
+    pub mutation: String,
+    pub outcome: Outcome,
+}
+
+#[derive(Clone, Debug)]
+pub struct TamperReport {
+    pub demo: &'static str,
+    pub results: Vec<TamperResult>,
+}
+
+impl TamperReport {
+    // 被接受或者使verifier panic的修改
+    pub fn failures(&self) -> Vec<&TamperResult> {
+        self.results
+            .iter()
+            .filter(|result| !result.outcome.is_rejected())
+            .collect()
+    }
+
+    // 每个字段的修改次数和被拒绝的次数
+    pub fn coverage(&self) -> BTreeMap<&str, Coverage> {
+        let mut coverage = BTreeMap::new();
+        for result in &self.results {
+            let entry: &mut Coverage = coverage.entry(result.field.as_str()).or_default();
+            entry.total += 1;
+            match result.outcome {
+                Outcome::RejectedByCheck => entry.by_check += 1,
+                Outcome::RejectedByVerifier => entry.by_verifier += 1,
+                Outcome::Panicked | Outcome::Accepted => {}
+            }
+        }
+        coverage
+    }
+}
+
+impl std::fmt::Display for TamperReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {} mutations", self.demo, self.results.len())?;
+        writeln!(
+            f,
+            "    {:<40} {:>7} {:>8} {:>8}",
+            "field", "total", "check", "verifier"
+        )?;
+        for (field, coverage) in self.coverage() {
+            writeln!(
+                f,
+                "    {:<40} {:>7} {:>8} {:>8}",
+                field, coverage.total, coverage.by_check, coverage.by_verifier
+            )?;
+        }
+        for result in &self.results {
+            match result.outcome {
+                Outcome::RejectedByCheck | Outcome::RejectedByVerifier => {}
+                Outcome::Panicked => {
+                    writeln!(f, "    PANICKED: {} {}", result.field, result.mutation)?
+                }
+                Outcome::Accepted => {
+                    writeln!(f, "    ACCEPTED: {} {}", result.field, result.mutation)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn tampering_proof_config() -> ProofConfig {
+    ProofConfig {
+        pow_bits: TAMPERING_POW_BITS,
+        ..ProofProfile::Demo.proof_config()
+    }
+}
+
+// 第一个、中间和最后一个
+fn sample(len: usize) -> Vec<usize> {
+    let mut indices = vec![0, len / 2, len.saturating_sub(1)];
+    indices.dedup();
+    indices.retain(|index| *index < len);
+    indices
+}
+
+fn bump(el: &mut F) {
+    el.add_assign(&F::ONE);
+}
+
+// 每次FRI查询中的oracle查询：witness, stage_2, quotient, setup，之后是每一层fri
+fn oracle_query_name(index: usize) -> String {
+    match index {
+        0 => "witness_query".to_string(),
+        1 => "stage_2_query".to_string(),
+        2 => "quotient_query".to_string(),
+        3 => "setup_query".to_string(),
+        _ => format!("fri_queries[{}]", index - 4),
+    }
+}
+
+fn oracle_query(queries: &SingleRoundQueries<F, H>, index: usize) -> &OracleQuery<F, H> {
+    match index {
+        0 => &queries.witness_query,
+        1 => &queries.stage_2_query,
+        2 => &queries.quotient_query,
+        3 => &queries.setup_query,
+        _ => &queries.fri_queries[index - 4],
+    }
+}
+
+fn oracle_query_mut(
+    queries: &mut SingleRoundQueries<F, H>,
+    index: usize,
+) -> &mut OracleQuery<F, H> {
+    match index {
+        0 => &mut queries.witness_query,
+        1 => &mut queries.stage_2_query,
+        2 => &mut queries.quotient_query,
+        3 => &mut queries.setup_query,
+        _ => &mut queries.fri_queries[index - 4],
+    }
+}
+
+struct Mutations<'a> {
+    proof: &'a Proof<F, H, EXT>,
+    vk: &'a VerificationKey<F, H>,
+    tampered: Vec<Tampered>,
+}
+
+impl<'a> Mutations<'a> {
+    fn push_proof(
+        &mut self,
+        field: &str,
+        mutation: String,
+        apply: impl FnOnce(&mut Proof<F, H, EXT>),
+    ) {
+        let mut proof = self.proof.clone();
+        apply(&mut proof);
+        self.tampered.push(Tampered {
+            field: field.to_string(),
+            mutation,
+            proof,
+            vk: None,
+        });
+    }
+
+    fn push_vk(
+        &mut self,
+        field: &str,
+        mutation: String,
+        apply: impl FnOnce(&mut VerificationKey<F, H>),
+    ) {
+        let mut vk = self.vk.clone();
+        apply(&mut vk);
+        self.tampered.push(Tampered {
+            field: field.to_string(),
+            mutation,
+            proof: self.proof.clone(),
+            vk: Some(vk),
+        });
+    }
+
+    fn elements(
+        &mut self,
+        field: &str,
+        location: &str,
+        len: usize,
+        get: impl Fn(&mut Proof<F, H, EXT>) -> &mut [F],
+    ) {
+        for index in sample(len) {
+            self.push_proof(
+                field,
+                format!("{}element {} + 1", location, index),
+                |proof| bump(&mut get(proof)[index]),
+            );
+        }
+    }
+
+    fn ext_elements(
+        &mut self,
+        field: &str,
+        len: usize,
+        get: impl Fn(&mut Proof<F, H, EXT>) -> &mut [ExtensionField<F, 2, EXT>],
+    ) {
+        for index in sample(len) {
+            for coeff in 0..2 {
+                self.push_proof(
+                    field,
+                    format!("element {} c{} + 1", index, coeff),
+                    |proof| bump(&mut get(proof)[index].coeffs[coeff]),
+                );
+            }
+        }
+    }
+
+    fn cap(
+        &mut self,
+        field: &str,
+        location: &str,
+        len: usize,
+        get: impl Fn(&mut Proof<F, H, EXT>) -> &mut [[F; 4]],
+    ) {
+        for index in sample(len) {
+            self.push_proof(
+                field,
+                format!("{}digest {} + 1", location, index),
+                |proof| bump(&mut get(proof)[index][0]),
+            );
+        }
+        if len > 1 {
+            self.push_proof(
+                field,
+                format!("{}swap digests 0 and {}", location, len - 1),
+                |proof| get(proof).swap(0, len - 1),
+            );
+        }
+    }
+}
+
+pub fn mutations(proof: &Proof<F, H, EXT>, vk: &VerificationKey<F, H>) -> Vec<Tampered> {
+    let mut m = Mutations {
+        proof,
+        vk,
+        tampered: vec![],
+    };
+
+    m.elements("public_inputs", "", proof.public_inputs.len(), |proof| {
+        &mut proof.public_inputs[..]
+    });
+    m.push_proof("public_inputs", "append 0".to_string(), |proof| {
+        proof.public_inputs.push(F::ZERO)
+    });
+    if !proof.public_inputs.is_empty() {
+        m.push_proof("public_inputs", "remove last".to_string(), |proof| {
+            proof.public_inputs.pop();
+        });
+    }
+
+    m.cap(
+        "witness_oracle_cap",
+        "",
+        proof.witness_oracle_cap.len(),
+        |proof| &mut proof.witness_oracle_cap[..],
+    );
+    m.cap(
+        "stage_2_oracle_cap",
+        "",
+        proof.stage_2_oracle_cap.len(),
+        |proof| &mut proof.stage_2_oracle_cap[..],
+    );
+    m.cap(
+        "quotient_oracle_cap",
+        "",
+        proof.quotient_oracle_cap.len(),
+        |proof| &mut proof.quotient_oracle_cap[..],
+    );
+
+    for (i, monomials) in proof.final_fri_monomials.iter().enumerate() {
+        m.elements(
+            "final_fri_monomials",
+            &format!("c{}, ", i),
+            monomials.len(),
+            |proof| &mut proof.final_fri_monomials[i][..],
+        );
+    }
+
+    m.ext_elements("values_at_z", proof.values_at_z.len(), |proof| {
+        &mut proof.values_at_z[..]
+    });
+    m.ext_elements(
+        "values_at_z_omega",
+        proof.values_at_z_omega.len(),
+        |proof| &mut proof.values_at_z_omega[..],
+    );
+    m.ext_elements("values_at_0", proof.values_at_0.len(), |proof| {
+        &mut proof.values_at_0[..]
+    });
+
+    m.cap(
+        "fri_base_oracle_cap",
+        "",
+        proof.fri_base_oracle_cap.len(),
+        |proof| &mut proof.fri_base_oracle_cap[..],
+    );
+    for (layer, cap) in proof.fri_intermediate_oracles_caps.iter().enumerate() {
+        m.cap(
+            "fri_intermediate_oracles_caps",
+            &format!("layer {}, ", layer),
+            cap.len(),
+            |proof| &mut proof.fri_intermediate_oracles_caps[layer][..],
+        );
+    }
+
+    for repetition in sample(proof.queries_per_fri_repetition.len()) {
+        let queries = &proof.queries_per_fri_repetition[repetition];
+        for index in 0..4 + queries.fri_queries.len() {
+            let name = oracle_query_name(index);
+            let query = oracle_query(queries, index);
+            let location = format!("repetition {}, ", repetition);
+
+            m.elements(
+                &format!("{}.leaf_elements", name),
+                &location,
+                query.leaf_elements.len(),
+                |proof| {
+                    &mut oracle_query_mut(&mut proof.queries_per_fri_repetition[repetition], index)
+                        .leaf_elements[..]
+                },
+            );
+            m.cap(
+                &format!("{}.proof", name),
+                &location,
+                query.proof.len(),
+                |proof| {
+                    &mut oracle_query_mut(&mut proof.queries_per_fri_repetition[repetition], index)
+                        .proof[..]
+                },
+            );
+            if !query.proof.is_empty() {
+                m.push_proof(
+                    &format!("{}.proof", name),
+                    format!("{}remove last digest", location),
+                    |proof| {
+                        oracle_query_mut(&mut proof.queries_per_fri_repetition[repetition], index)
+                            .proof
+                            .pop();
+                    },
+                );
+            }
+        }
+    }
+
+    m.push_proof(
+        "queries_per_fri_repetition",
+        "remove last repetition".to_string(),
+        |proof| {
+            proof.queries_per_fri_repetition.pop();
+        },
+    );
+    if !proof.queries_per_fri_repetition[0].fri_queries.is_empty() {
+        m.push_proof(
+            "queries_per_fri_repetition",
+            "repetition 0, remove last fri layer".to_string(),
+            |proof| {
+                proof.queries_per_fri_repetition[0].fri_queries.pop();
+            },
+        );
+    }
+
+    m.push_proof("pow_challenge", "+ 1".to_string(), |proof| {
+        proof.pow_challenge = proof.pow_challenge.wrapping_add(1)
+    });
+    m.push_proof("pow_challenge", "flip bit 63".to_string(), |proof| {
+        proof.pow_challenge ^= 1 << 63
+    });
+
+    // 降低security_level，删除多余的查询，使proof与修改后的参数一致
+    let mut weaker = proof.proof_config.clone();
+    weaker.security_level -= 20;
+    let weaker_queries = num_fri_queries(&weaker);
+    m.push_proof(
+        "proof_config",
+        format!(
+            "security_level {} and {} queries",
+            weaker.security_level, weaker_queries
+        ),
+        |proof| {
+            proof.proof_config = weaker;
+            proof.queries_per_fri_repetition.truncate(weaker_queries);
+        },
+    );
+    // pow_bits降为0，同时降低security_level使查询次数不变：verifier不再检查nonce
+    m.push_proof(
+        "proof_config",
+        "pow_bits 0 with the same number of queries".to_string(),
+        |proof| {
+            proof.proof_config.security_level -= proof.proof_config.pow_bits as usize;
+            proof.proof_config.pow_bits = 0;
+        },
+    );
+    m.push_proof("proof_config", "fri_lde_factor / 2".to_string(), |proof| {
+        proof.proof_config.fri_lde_factor /= 2
+    });
+    m.push_proof(
+        "proof_config",
+        "merkle_tree_cap_size * 2".to_string(),
+        |proof| proof.proof_config.merkle_tree_cap_size *= 2,
+    );
+
+    let cap_len = vk.setup_merkle_tree_cap.len();
+    for index in sample(cap_len) {
+        m.push_vk(
+            "vk.setup_merkle_tree_cap",
+            format!("digest {} + 1", index),
+            |vk| bump(&mut vk.setup_merkle_tree_cap[index][0]),
+        );
+    }
+    if cap_len > 1 {
+        m.push_vk(
+            "vk.setup_merkle_tree_cap",
+            format!("swap digests 0 and {}", cap_len - 1),
+            |vk| vk.setup_merkle_tree_cap.swap(0, cap_len - 1),
+        );
+    }
+
+    m.tampered
+}
+
+pub fn tamper<D: DemoCircuit>(demo: &D, worker: &Worker) -> TamperReport {
+    let (proof, vk) = prove::<D, TR, H, POW>(demo, worker, tampering_proof_config());
+    let verifier = PreparedVerifier::new(demo, vk.clone());
+    assert!(
+        verifier.verify::<TR, POW>(&proof),
+        "{}: 原来的proof不能通过验证",
+        demo.name()
+    );
+
+    let results = mutations(&proof, &vk)
+        .into_iter()
+        .map(|tampered| {
+            let outcome = catch_unwind(AssertUnwindSafe(|| {
+                let checked_vk = tampered.vk.as_ref().unwrap_or(&vk);
+                if check_proof(demo, checked_vk, &tampered.proof, &tampering_proof_config())
+                    .is_err()
+                {
+                    return Outcome::RejectedByCheck;
+                }
+                let is_valid = match &tampered.vk {
+                    Some(vk) => verify::<D, TR, H, POW>(demo, vk, &tampered.proof),
+                    None => verifier.verify::<TR, POW>(&tampered.proof),
+                };
+                if is_valid {
+                    Outcome::Accepted
+                } else {
+                    Outcome::RejectedByVerifier
+                }
+            }))
+            .unwrap_or(Outcome::Panicked);
+
+            TamperResult {
+                field: tampered.field,
+                mutation: tampered.mutation,
+                outcome,
+            }
+        })
+        .collect();
+
+    TamperReport {
+        demo: demo.name(),
+        results,
+    }
+}
@@ -0,0 +1,366 @@
+// 导出电路表格
+//
+// 表格按README中的划分：
+// - variable列（通用列，参与copy permutation）
+// - witness列（不参与copy permutation）
+// - 常量列：前面几列是压缩后的selector，后面是gate使用的常量
+// - specialized列：lookup等单独放置的gate，单独显示
+// 同一个variable出现在多个单元格时，这些单元格构成一个copy permutation的环
+
+use std::{collections::HashMap, fmt::Write};
+
+use boojum::{
+    config::DevCSConfig,
+    cs::{
+        implementations::{reference_cs::CSReferenceAssembly, setup::TreeNode},
+        Place,
+    },
+    dag::WitnessSource,
+    field::U64Representable,
+    worker::Worker,
+};
+
+use super::demos::F;
+
+// 表格中的一个单元格：variable或witness的编号和值，没有放置时为None
+pub type TraceCell = Option<(u32, F)>;
+
+#[derive(Clone, Debug)]
+pub struct TraceRow {
+    pub gate: usize,
+    pub variables: Vec<TraceCell>,
+    pub witnesses: Vec<TraceCell>,
+    pub selectors: Vec<F>,
+    pub constants: Vec<F>,
+    pub specialized: Vec<TraceCell>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TraceTable {
+    pub gate_names: Vec<String>,
+    pub rows: Vec<TraceRow>,
+}
+
+// 从selector树中找到gate的深度，也就是它使用的selector列数
+fn selector_depth(node: &TreeNode, gate_idx: usize) -> Option<usize> {
+    match node {
+        TreeNode::Empty => None,
+        TreeNode::GateOnly(description) => (description.gate_idx == gate_idx).then_some(0),
+        TreeNode::Fork { left, right } => selector_depth(left, gate_idx)
+            .or_else(|| selector_depth(right, gate_idx))
+            .map(|depth| depth + 1),
+    }
+}
+
+fn variable_cell(
+    cs: &CSReferenceAssembly<F, F, DevCSConfig>,
+    column: usize,
+    row: usize,
+) -> TraceCell {
+    let variable = cs.copy_permutation_data[column][row];
+    if variable.is_placeholder() {
+        return None;
+    }
+
+    Some((
+        variable.as_variable_index(),
+        cs.get_value_unchecked(Place::from_variable(variable)),
+    ))
+}
+
+pub fn extract_trace(cs: &CSReferenceAssembly<F, F, DevCSConfig>, worker: &Worker) -> TraceTable {
+    let num_general_purpose_columns = cs.parameters.num_columns_under_copy_permutation;
+    let num_columns = cs.copy_permutation_data.len();
+    let evaluation_data = &cs.evaluation_data_over_general_purpose_columns;
+
+    let (constant_columns, selectors_placement, _) = cs.create_constant_setup_polys(worker);
+
+    let gate_names = evaluation_data
+        .evaluators_over_general_purpose_columns
+        .iter()
+        .map(|evaluator| evaluator.debug_name.clone())
+        .collect();
+
+    let rows = cs
+        .gates_application_sets
+        .iter()
+        .enumerate()
+        .map(|(row, gate)| {
+            let constants: Vec<F> = constant_columns
+                .iter()
+                .map(|column| column.storage[row])
+                .collect();
+            let depth = selector_depth(&selectors_placement, *gate).unwrap_or(0);
+
+            TraceRow {
+                gate: *gate,
+                variables: (0..num_general_purpose_columns)
+                    .map(|column| variable_cell(cs, column, row))
+                    .collect(),
+                witnesses: cs
+                    .witness_placement_data
+                    .iter()
+                    .map(|column| {
+                        let witness = column[row];
+                        (!witness.is_placeholder()).then(|| {
+                            (
+                                witness.as_witness_index(),
+                                cs.get_value_unchecked(Place::from_witness(witness)),
+                            )
+                        })
+                    })
+                    .collect(),
+                selectors: constants[..depth].to_vec(),
+                constants: constants[depth..].to_vec(),
+                specialized: (num_general_purpose_columns..num_columns)
+                    .map(|column| variable_cell(cs, column, row))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    TraceTable { gate_names, rows }
+}
+
+fn format_cell(cell: &TraceCell) -> String {
+    match cell {
+        Some((index, value)) => format!("v{}={}", index, value.as_u64_reduced()),
+        None => String::new(),
+    }
+}
+
+impl TraceTable {
+    fn max_len(&self, f: impl Fn(&TraceRow) -> usize) -> usize {
+        self.rows.iter().map(f).max().unwrap_or(0)
+    }
+
+    // 每个variable出现的次数，出现多于一次的构成copy permutation的环
+    pub fn copy_cycles(&self) -> HashMap<u32, usize> {
+        let mut cycles = HashMap::new();
+        for row in &self.rows {
+            for (index, _) in row.variables.iter().chain(&row.specialized).flatten() {
+                *cycles.entry(*index).or_insert(0) += 1;
+            }
+        }
+        cycles.retain(|_, len| *len > 1);
+        cycles
+    }
+
+    pub fn to_csv(&self) -> String {
+        let num_variables = self.max_len(|row| row.variables.len());
+        let num_witnesses = self.max_len(|row| row.witnesses.len());
+        let num_selectors = self.max_len(|row| row.selectors.len());
+        let num_constants = self.max_len(|row| row.constants.len());
+        let num_specialized = self.max_len(|row| row.specialized.len());
+
+        let mut header = vec!["row".to_string(), "gate".to_string()];
+        header.extend((0..num_variables).map(|i| format!("variable_{}", i)));
+        header.extend((0..num_witnesses).map(|i| format!("witness_{}", i)));
+        header.extend((0..num_selectors).map(|i| format!("selector_{}", i)));
+        header.extend((0..num_constants).map(|i| format!("constant_{}", i)));
+        header.extend((0..num_specialized).map(|i| format!("specialized_{}", i)));
+
+        let mut out = header.join(",");
+        out.push('\n');
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            let mut fields = vec![row_idx.to_string(), self.gate_names[row.gate].clone()];
+            let pad = |cells: Vec<String>, len: usize| {
+                let mut cells = cells;
+                cells.resize(len, String::new());
+                cells
+            };
+            fields.extend(pad(
+                row.variables.iter().map(format_cell).collect(),
+                num_variables,
+            ));
+            fields.extend(pad(
+                row.witnesses.iter().map(format_cell).collect(),
+                num_witnesses,
+            ));
+            fields.extend(pad(
+                row.selectors
+                    .iter()
+                    .map(|el| el.as_u64_reduced().to_string())
+                    .collect(),
+                num_selectors,
+            ));
+            fields.extend(pad(
+                row.constants
+                    .iter()
+                    .map(|el| el.as_u64_reduced().to_string())
+                    .collect(),
+                num_constants,
+            ));
+            fields.extend(pad(
+                row.specialized.iter().map(format_cell).collect(),
+                num_specialized,
+            ));
+
+            // gate名字中可能有逗号
+            let fields: Vec<String> = fields
+                .into_iter()
+                .map(|field| {
+                    if field.contains(',') {
+                        format!("\"{}\"", field.replace('"', "\"\""))
+                    } else {
+                        field
+                    }
+                })
+                .collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    // 不依赖外部资源的静态页面：每种gate一个颜色，鼠标移到单元格上时高亮同一个copy permutation环
+    pub fn to_html(&self, title: &str) -> String {
+        let cycles = self.copy_cycles();
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+        let color = |gate: usize| format!("hsl({}, 70%, 90%)", (gate * 67) % 360);
+
+        let mut out = String::new();
+        writeln!(out, "<!DOCTYPE html>").unwrap();
+        writeln!(
+            out,
+            "<html><head><meta charset=\"utf-8\"><title>{}</title>",
+            escape(title)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "<style>\
+             body {{ font-family: monospace; font-size: 12px; }}\
+             table {{ border-collapse: collapse; }}\
+             td, th {{ border: 1px solid #ccc; padding: 2px 4px; white-space: nowrap; }}\
+             td.cycle {{ font-weight: bold; }}\
+             td.active {{ outline: 2px solid #d00; }}\
+             th.section {{ background: #eee; }}\
+             </style></head><body>"
+        )
+        .unwrap();
+        writeln!(out, "<h1>{}</h1>", escape(title)).unwrap();
+
+        writeln!(out, "<h2>gates</h2><ul>").unwrap();
+        for (gate, name) in self.gate_names.iter().enumerate() {
+            let num_rows = self.rows.iter().filter(|row| row.gate == gate).count();
+            writeln!(
+                out,
+                "<li style=\"background: {}\">{} ({} rows)</li>",
+                color(gate),
+                escape(name),
+                num_rows
+            )
+            .unwrap();
+        }
+        writeln!(out, "</ul>").unwrap();
+
+        let cell_html = |cell: &TraceCell| match cell {
+            Some((index, value)) if cycles.contains_key(index) => format!(
+                "<td class=\"cycle\" data-var=\"{}\" title=\"v{}, {} copies\">{}</td>",
+                index,
+                index,
+                cycles[index],
+                value.as_u64_reduced()
+            ),
+            Some((index, value)) => {
+                format!("<td title=\"v{}\">{}</td>", index, value.as_u64_reduced())
+            }
+            None => "<td></td>".to_string(),
+        };
+
+        let sections: [(&str, fn(&TraceRow) -> usize); 4] = [
+            ("variables", |row| row.variables.len()),
+            ("witnesses", |row| row.witnesses.len()),
+            ("selectors", |row| row.selectors.len()),
+            ("constants", |row| row.constants.len()),
+        ];
+        let widths: Vec<usize> = sections.iter().map(|(_, f)| self.max_len(f)).collect();
+
+        writeln!(out, "<h2>general purpose columns</h2><table>").unwrap();
+        write!(out, "<tr><th>row</th><th>gate</th>").unwrap();
+        for ((name, _), width) in sections.iter().zip(&widths) {
+            if *width > 0 {
+                write!(
+                    out,
+                    "<th class=\"section\" colspan=\"{}\">{}</th>",
+                    width, name
+                )
+                .unwrap();
+            }
+        }
+        writeln!(out, "</tr>").unwrap();
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            write!(
+                out,
+                "<tr style=\"background: {}\"><td>{}</td><td>{}</td>",
+                color(row.gate),
+                row_idx,
+                escape(&self.gate_names[row.gate])
+            )
+            .unwrap();
+            let mut cells: Vec<String> = vec![];
+            let mut push = |mut section: Vec<String>, width: usize| {
+                section.resize(width, "<td></td>".to_string());
+                cells.extend(section);
+            };
+            push(row.variables.iter().map(cell_html).collect(), widths[0]);
+            push(row.witnesses.iter().map(cell_html).collect(), widths[1]);
+            push(
+                row.selectors
+                    .iter()
+                    .map(|el| format!("<td>{}</td>", el.as_u64_reduced()))
+                    .collect(),
+                widths[2],
+            );
+            push(
+                row.constants
+                    .iter()
+                    .map(|el| format!("<td>{}</td>", el.as_u64_reduced()))
+                    .collect(),
+                widths[3],
+            );
+            writeln!(out, "{}</tr>", cells.concat()).unwrap();
+        }
+        writeln!(out, "</table>").unwrap();
+
+        let num_specialized = self.max_len(|row| row.specialized.len());
+        if num_specialized > 0 {
+            writeln!(out, "<h2>specialized columns (lookups)</h2><table>").unwrap();
+            writeln!(
+                out,
+                "<tr><th>row</th><th class=\"section\" colspan=\"{}\">variables</th></tr>",
+                num_specialized
+            )
+            .unwrap();
+            for (row_idx, row) in self.rows.iter().enumerate() {
+                if row.specialized.iter().all(|cell| cell.is_none()) {
+                    continue;
+                }
+                let cells: Vec<String> = row.specialized.iter().map(cell_html).collect();
+                writeln!(out, "<tr><td>{}</td>{}</tr>", row_idx, cells.concat()).unwrap();
+            }
+            writeln!(out, "</table>").unwrap();
+        }
+
+        writeln!(
+            out,
+            "<script>\
+             document.querySelectorAll('td.cycle').forEach(td => {{\
+               const cells = document.querySelectorAll('td[data-var=\"' + td.dataset.var + '\"]');\
+               td.addEventListener('mouseenter', () => cells.forEach(c => c.classList.add('active')));\
+               td.addEventListener('mouseleave', () => cells.forEach(c => c.classList.remove('active')));\
+             }});\
+             </script>"
+        )
+        .unwrap();
+        writeln!(out, "</body></html>").unwrap();
+
+        out
+    }
+}
@@ -0,0 +1,442 @@
+// 找出没有被约束住的variable，作为安全审查的第一步
+//
+// 三种检查：
+// - 结构：分配了但没有放置在任何一列中的variable和witness
+// - 系数：每次出现时改变它的值都不影响gate的约束，例如Fma中coeff_for_quadtaric_part为0时的a和b
+// - 扰动：随机改变一个不由gate计算出来的variable，按gate重新计算依赖它的variable，
+//   如果所有约束仍然成立并且public input不变，再把新的值写入assembly，用check_if_satisfied确认
+//
+// 只按debugger中支持的gate计算，重新计算只支持只有一个输出、输出为线性的gate（Fma, Reduction, Constants）；
+// lookup等specialized列上的约束只在最后的check_if_satisfied中检查
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use boojum::{
+    config::DevCSConfig,
+    cs::{implementations::reference_cs::CSReferenceAssembly, Place, Variable},
+    dag::{CircuitResolver, WitnessSource},
+    field::{Field, U64Representable},
+    worker::Worker,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    debugger::{gate_relation, GateRelation},
+    demos::{DemoCircuit, F},
+    harness::synthesize_assembly,
+};
+
+pub type Assembly = CSReferenceAssembly<F, F, DevCSConfig>;
+
+#[derive(Clone, Debug)]
+pub struct Occurrence {
+    pub gate: &'static str,
+    pub row: usize,
+    pub instance: usize,
+    // 在gate实例中的位置
+    pub position: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct IrrelevantVariable {
+    pub variable: u32,
+    pub value: F,
+    pub occurrences: Vec<Occurrence>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FreeWitness {
+    pub variable: u32,
+    pub original: F,
+    pub replacement: F,
+    // 随之重新计算的variable和它们新的值
+    pub changed: Vec<(u32, F)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UnderconstrainedReport {
+    // 没有放置的位置的编号
+    pub unplaced: Vec<u64>,
+    pub irrelevant: Vec<IrrelevantVariable>,
+    pub free: Vec<FreeWitness>,
+    pub unchecked_gates: Vec<String>,
+}
+
+impl UnderconstrainedReport {
+    pub fn is_fully_constrained(&self) -> bool {
+        self.unplaced.is_empty() && self.irrelevant.is_empty() && self.free.is_empty()
+    }
+}
+
+struct GateInstance {
+    relation: GateRelation,
+    row: usize,
+    instance: usize,
+    variables: Vec<u32>,
+    constants: Vec<F>,
+}
+
+impl GateInstance {
+    // 把position位置上的值换成value之后计算约束
+    fn terms(&self, values: &BTreeMap<u32, F>, replace: Option<(usize, F)>) -> Vec<F> {
+        let mut inputs: Vec<F> = self
+            .variables
+            .iter()
+            .map(|variable| values[variable])
+            .collect();
+        if let Some((position, value)) = replace {
+            inputs[position] = value;
+        }
+
+        (self.relation.evaluate)(&inputs, &self.constants)
+    }
+
+    fn output(&self) -> Option<u32> {
+        (self.relation.outputs > 0).then(|| self.variables[self.variables.len() - 1])
+    }
+}
+
+struct Placement {
+    values: BTreeMap<u32, F>,
+    gates: Vec<GateInstance>,
+    // 放置在specialized列或者不支持的gate中的variable，认为它们是被约束住的
+    bound_elsewhere: BTreeSet<u32>,
+    public_inputs: Vec<u32>,
+    unchecked_gates: Vec<String>,
+}
+
+fn placement(cs: &Assembly) -> Placement {
+    let num_general_purpose_columns = cs.parameters.num_columns_under_copy_permutation;
+    let evaluation_data = &cs.evaluation_data_over_general_purpose_columns;
+
+    let mut placement = Placement {
+        values: BTreeMap::new(),
+        gates: vec![],
+        bound_elsewhere: BTreeSet::new(),
+        public_inputs: vec![],
+        unchecked_gates: vec![],
+    };
+    for (column, variables) in cs.copy_permutation_data.iter().enumerate() {
+        for (row, variable) in variables.iter().enumerate() {
+            if variable.is_placeholder() {
+                continue;
+            }
+            let index = variable.as_variable_index();
+            placement
+                .values
+                .entry(index)
+                .or_insert_with(|| cs.get_value_unchecked(Place::from_variable(*variable)));
+            if column >= num_general_purpose_columns {
+                placement.bound_elsewhere.insert(index);
+            }
+            let gate_idx = cs.gates_application_sets[row];
+            let type_id = evaluation_data.gate_type_ids_for_general_purpose_columns[gate_idx];
+            if column < num_general_purpose_columns && gate_relation(type_id).is_none() {
+                placement.bound_elsewhere.insert(index);
+            }
+        }
+    }
+
+    for (row, gate_idx) in cs.gates_application_sets.iter().enumerate() {
+        let type_id = evaluation_data.gate_type_ids_for_general_purpose_columns[*gate_idx];
+        let Some(relation) = gate_relation(type_id) else {
+            let name =
+                &evaluation_data.evaluators_over_general_purpose_columns[*gate_idx].debug_name;
+            if !placement.unchecked_gates.contains(name) {
+                placement.unchecked_gates.push(name.clone());
+            }
+            continue;
+        };
+        if relation.name == "NopGate" {
+            continue;
+        }
+
+        for instance in 0..num_general_purpose_columns / relation.width {
+            let variables: Vec<Variable> = (instance * relation.width
+                ..(instance + 1) * relation.width)
+                .map(|column| cs.copy_permutation_data[column][row])
+                .collect();
+            if variables.iter().any(|variable| variable.is_placeholder()) {
+                continue;
+            }
+            let variables: Vec<u32> = variables
+                .iter()
+                .map(|variable| variable.as_variable_index())
+                .collect();
+            if relation.name == "PublicInputGate" {
+                placement.public_inputs.push(variables[0]);
+            }
+
+            placement.gates.push(GateInstance {
+                relation,
+                row,
+                instance,
+                variables,
+                constants: relation
+                    .instance_constants(&cs.constants_requested_per_row[row], instance)
+                    .to_vec(),
+            });
+        }
+    }
+
+    placement
+}
+
+fn random_element(rng: &mut StdRng) -> F {
+    F::from_u64_with_reduction(rng.gen())
+}
+
+fn find_irrelevant(placement: &Placement, rng: &mut StdRng) -> Vec<IrrelevantVariable> {
+    let mut occurrences: BTreeMap<u32, Vec<(Occurrence, bool)>> = BTreeMap::new();
+    for gate in &placement.gates {
+        let original = gate.terms(&placement.values, None);
+        for (position, variable) in gate.variables.iter().enumerate() {
+            // public input的值由verifier给出，总是被约束住的
+            let is_relevant = gate.relation.name == "PublicInputGate" || {
+                let mut shifted = placement.values[variable];
+                shifted.add_assign(&F::ONE);
+                [shifted, random_element(rng)]
+                    .into_iter()
+                    .any(|value| gate.terms(&placement.values, Some((position, value))) != original)
+            };
+            occurrences.entry(*variable).or_default().push((
+                Occurrence {
+                    gate: gate.relation.name,
+                    row: gate.row,
+                    instance: gate.instance,
+                    position,
+                },
+                is_relevant,
+            ));
+        }
+    }
+
+    occurrences
+        .into_iter()
+        .filter(|(variable, occurrences)| {
+            !placement.bound_elsewhere.contains(variable)
+                && occurrences.iter().all(|(_, is_relevant)| !is_relevant)
+        })
+        .map(|(variable, occurrences)| IrrelevantVariable {
+            variable,
+            value: placement.values[&variable],
+            occurrences: occurrences
+                .into_iter()
+                .map(|(occurrence, _)| occurrence)
+                .collect(),
+        })
+        .collect()
+}
+
+// 重新计算只有一个输出的gate的输出，直到不再变化
+fn propagate(gates: &[GateInstance], values: &mut BTreeMap<u32, F>) {
+    for _ in 0..=gates.len() {
+        let mut changed = false;
+        for gate in gates.iter().filter(|gate| gate.relation.outputs == 1) {
+            let position = gate.variables.len() - 1;
+            let output = gate.variables[position];
+            let current = values[&output];
+            let residual = gate.terms(values, None)[0];
+            if residual.is_zero() {
+                continue;
+            }
+
+            // 约束对输出是线性的：residual + slope * (x - current) = 0
+            let mut next = current;
+            next.add_assign(&F::ONE);
+            let mut slope = gate.terms(values, Some((position, next)))[0];
+            slope.sub_assign(&residual);
+            let Some(inverse) = slope.inverse() else {
+                continue;
+            };
+            let mut solved = residual;
+            solved.mul_assign(&inverse);
+            let mut value = current;
+            value.sub_assign(&solved);
+
+            values.insert(output, value);
+            changed = true;
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+// assembly没有修改witness的接口，直接改写resolver中保存的值
+fn set_value(cs: &mut Assembly, variable: u32, value: F) {
+    let place = Place::from_variable(Variable::from_variable_index(variable as u64));
+    cs.witness_resolver.set_value(place, value);
+}
+
+// 把报告的替换写入新的assembly，可以用来复现结果
+pub fn apply_free_witness<D: DemoCircuit>(demo: &D, witness: &FreeWitness) -> Assembly {
+    let mut cs = synthesize_assembly(demo);
+    set_value(&mut cs, witness.variable, witness.replacement);
+    for (variable, value) in &witness.changed {
+        set_value(&mut cs, *variable, *value);
+    }
+
+    cs
+}
+
+fn confirm<D: DemoCircuit>(
+    demo: &D,
+    worker: &Worker,
+    original: &BTreeMap<u32, F>,
+    perturbed: &BTreeMap<u32, F>,
+) -> bool {
+    let mut cs = synthesize_assembly(demo);
+    for (variable, value) in perturbed {
+        if original[variable] != *value {
+            set_value(&mut cs, *variable, *value);
+        }
+    }
+
+    cs.check_if_satisfied(worker)
+}
+
+fn find_free<D: DemoCircuit>(
+    demo: &D,
+    worker: &Worker,
+    placement: &Placement,
+    trials: usize,
+    rng: &mut StdRng,
+) -> Vec<FreeWitness> {
+    let outputs: BTreeSet<u32> = placement
+        .gates
+        .iter()
+        .filter_map(|gate| gate.output())
+        .collect();
+
+    let mut free = vec![];
+    for (variable, original) in &placement.values {
+        if outputs.contains(variable) {
+            continue;
+        }
+
+        let mut candidates = vec![F::ZERO, F::ONE, *original, *original];
+        candidates[2].add_assign(&F::ONE);
+        candidates[3].sub_assign(&F::ONE);
+        candidates.extend((0..trials).map(|_| random_element(rng)));
+
+        for candidate in candidates {
+            if candidate == *original {
+                continue;
+            }
+            let mut perturbed = placement.values.clone();
+            perturbed.insert(*variable, candidate);
+            propagate(&placement.gates, &mut perturbed);
+
+            let is_satisfied = placement.gates.iter().all(|gate| {
+                gate.terms(&perturbed, None)
+                    .iter()
+                    .all(|term| term.is_zero())
+            });
+            let same_public_inputs = placement
+                .public_inputs
+                .iter()
+                .all(|input| perturbed[input] == placement.values[input]);
+            if !is_satisfied
+                || !same_public_inputs
+                || !confirm(demo, worker, &placement.values, &perturbed)
+            {
+                continue;
+            }
+
+            free.push(FreeWitness {
+                variable: *variable,
+                original: *original,
+                replacement: candidate,
+                changed: perturbed
+                    .iter()
+                    .filter(|(other, value)| {
+                        *other != variable && placement.values[*other] != **value
+                    })
+                    .map(|(other, value)| (*other, *value))
+                    .collect(),
+            });
+            break;
+        }
+    }
+
+    free
+}
+
+// trials为每个variable额外尝试的随机值的个数
+pub fn find_underconstrained<D: DemoCircuit>(
+    demo: &D,
+    worker: &Worker,
+    trials: usize,
+) -> UnderconstrainedReport {
+    let cs = synthesize_assembly(demo);
+    let placement = placement(&cs);
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let placed: BTreeSet<u64> = cs
+        .copy_permutation_data
+        .iter()
+        .flatten()
+        .filter(|variable| !variable.is_placeholder())
+        .map(|variable| variable.as_variable_index() as u64)
+        .chain(
+            cs.witness_placement_data
+                .iter()
+                .flatten()
+                .filter(|witness| !witness.is_placeholder())
+                .map(|witness| witness.as_witness_index() as u64),
+        )
+        .collect();
+
+    UnderconstrainedReport {
+        unplaced: (0..cs.next_available_place_idx)
+            .filter(|index| !placed.contains(index))
+            .collect(),
+        irrelevant: find_irrelevant(&placement, &mut rng),
+        free: find_free(demo, worker, &placement, trials, &mut rng),
+        unchecked_gates: placement.unchecked_gates,
+    }
+}
+
+impl std::fmt::Display for UnderconstrainedReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for index in &self.unplaced {
+            writeln!(f, "place {} is allocated but never placed", index)?;
+        }
+        for variable in &self.irrelevant {
+            writeln!(
+                f,
+                "v{} = {} does not affect any constraint it appears in:",
+                variable.variable,
+                variable.value.as_u64_reduced()
+            )?;
+            for occurrence in &variable.occurrences {
+                writeln!(
+                    f,
+                    "    {} at row {}, instance {}, position {}",
+                    occurrence.gate, occurrence.row, occurrence.instance, occurrence.position
+                )?;
+            }
+        }
+        for witness in &self.free {
+            write!(
+                f,
+                "v{} can be changed from {} to {}",
+                witness.variable,
+                witness.original.as_u64_reduced(),
+                witness.replacement.as_u64_reduced()
+            )?;
+            for (variable, value) in &witness.changed {
+                write!(f, ", v{} -> {}", variable, value.as_u64_reduced())?;
+            }
+            writeln!(f)?;
+        }
+        if !self.unchecked_gates.is_empty() {
+            writeln!(f, "unchecked gates: {:?}", self.unchecked_gates)?;
+        }
+        if self.is_fully_constrained() {
+            writeln!(f, "no underconstrained variables found")?;
+        }
+        Ok(())
+    }
+}
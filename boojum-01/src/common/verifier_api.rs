@@ -0,0 +1,400 @@
+// 只包含验证的部分：按circuit_id选择demo的配置，解码vk和proof（serde_json），用CsVerifierBuilder验证
+//
+// 这里不使用allocator_api、Worker和线程，可以在wasm32上编译（见wasm_verifier.rs）
+// 只支持demo中使用的Poseidon transcript和证明参数（demos::demo_proof_config），不做grinding。
+// vk和proof都是不可信的输入：形状不对时boojum的verifier会panic，而wasm中panic无法恢复，
+// 所以在调用verifier之前检查参数和proof中每一部分的长度
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidonSponge},
+    cs::{
+        cs_builder::new_builder,
+        cs_builder_verifier::CsVerifierBuilder,
+        implementations::{
+            pow::NoPow,
+            proof::Proof,
+            prover::{compute_fri_schedule, ProofConfig},
+            transcript::GoldilocksPoisedonTranscript,
+            verifier::VerificationKey,
+        },
+        LookupParameters,
+    },
+    field::{goldilocks::GoldilocksExt2, U64Representable},
+};
+
+use super::demos::{
+    demo_proof_config, BooleanDemo, ConstantFibonacciDemo, DemoCircuit, FibonacciDemo, LookupDemo,
+    SimplePolyDemo, Uint8Demo, F,
+};
+
+type TR = GoldilocksPoisedonTranscript;
+type H = GoldilocksPoseidonSponge<AbsorptionModeOverwrite>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    UnknownCircuit(String),
+    InvalidVerificationKey(String),
+    InvalidProof(String),
+    // vk与circuit_id对应的电路参数不一致
+    GeometryMismatch,
+    // proof或vk的证明参数与要求的不一致，值为不一致的字段
+    ConfigMismatch(String),
+    // proof中某一部分的长度不对，值为这一部分的名字
+    MalformedProof(String),
+    VerificationFailed,
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::UnknownCircuit(id) => write!(f, "unknown circuit id: {}", id),
+            VerifyError::InvalidVerificationKey(err) => {
+                write!(f, "cannot decode verification key: {}", err)
+            }
+            VerifyError::InvalidProof(err) => write!(f, "cannot decode proof: {}", err),
+            VerifyError::GeometryMismatch => {
+                write!(f, "verification key does not match the circuit")
+            }
+            VerifyError::ConfigMismatch(field) => {
+                write!(f, "unsupported proof parameters: {}", field)
+            }
+            VerifyError::MalformedProof(field) => write!(f, "malformed proof: {}", field),
+            VerifyError::VerificationFailed => write!(f, "proof is invalid"),
+        }
+    }
+}
+
+// circuit_id与DemoCircuit::name()相同
+pub const CIRCUIT_IDS: [&str; 6] = [
+    "simple_fibonacci",
+    "prove_verify_fibonacci",
+    "simple_poly",
+    "boolean_demo",
+    "uint8_demo",
+    "lookup_demo",
+];
+
+fn check_config(
+    vk: &VerificationKey<F, H>,
+    proof: &Proof<F, H, GoldilocksExt2>,
+    expected: &ProofConfig,
+) -> Result<(), VerifyError> {
+    let config = &proof.proof_config;
+    let fixed = &vk.fixed_parameters;
+
+    let checks = [
+        (
+            "fri_lde_factor",
+            config.fri_lde_factor == expected.fri_lde_factor,
+        ),
+        (
+            "merkle_tree_cap_size",
+            config.merkle_tree_cap_size == expected.merkle_tree_cap_size,
+        ),
+        (
+            "fri_folding_schedule",
+            config.fri_folding_schedule == expected.fri_folding_schedule,
+        ),
+        (
+            "security_level",
+            config.security_level == expected.security_level,
+        ),
+        ("pow_bits", config.pow_bits == expected.pow_bits),
+        (
+            "vk fri_lde_factor",
+            fixed.fri_lde_factor == expected.fri_lde_factor,
+        ),
+        (
+            "vk cap_size",
+            fixed.cap_size == expected.merkle_tree_cap_size,
+        ),
+        (
+            "vk setup_merkle_tree_cap",
+            vk.setup_merkle_tree_cap.len() == expected.merkle_tree_cap_size,
+        ),
+    ];
+    match checks.iter().find(|(_, ok)| !ok) {
+        Some((field, _)) => Err(VerifyError::ConfigMismatch(field.to_string())),
+        None => Ok(()),
+    }
+}
+
+// lookup使用的specialized列：(变量列, 常量列, 子argument数, 表的宽度)
+// 只支持demo中使用的lookup参数
+fn lookup_columns(lookup: &LookupParameters) -> Result<(usize, usize, usize, usize), VerifyError> {
+    match *lookup {
+        LookupParameters::NoLookup => Ok((0, 0, 0, 0)),
+        LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+            width,
+            num_repetitions,
+            share_table_id,
+        } => {
+            let width = width as usize;
+            let table_id_columns = if share_table_id { 1 } else { num_repetitions };
+            Ok((
+                width * num_repetitions,
+                table_id_columns,
+                num_repetitions,
+                width,
+            ))
+        }
+        _ => Err(VerifyError::ConfigMismatch("lookup_parameters".to_string())),
+    }
+}
+
+// 由vk和电路参数决定的proof形状，与boojum的verifier使用的数量相同
+struct ProofShape {
+    values_at_z: usize,
+    values_at_z_omega: usize,
+    values_at_0: usize,
+    // 每个oracle一个leaf中的元素个数，扩域元素占两个
+    witness_leaf: usize,
+    stage_2_leaf: usize,
+    quotient_leaf: usize,
+    setup_leaf: usize,
+    num_queries: usize,
+    // 每个FRI oracle折叠的比特数
+    fri_schedule: Vec<usize>,
+    final_degree: usize,
+}
+
+fn expected_shape(
+    vk: &VerificationKey<F, H>,
+    config: &ProofConfig,
+) -> Result<ProofShape, VerifyError> {
+    let fixed = &vk.fixed_parameters;
+    let geometry = &fixed.parameters;
+    let domain_size = fixed.domain_size as usize;
+
+    // quotient的次数不能超过lde因子
+    let quotient_degree = fixed.quotient_degree;
+    if !quotient_degree.is_power_of_two() || quotient_degree > config.fri_lde_factor {
+        return Err(VerifyError::ConfigMismatch(
+            "vk quotient_degree".to_string(),
+        ));
+    }
+
+    let (lookup_variables, lookup_constants, num_subarguments, lookup_width) =
+        lookup_columns(&fixed.lookup_parameters)?;
+    let lookup_is_allowed = fixed.lookup_parameters != LookupParameters::NoLookup;
+    let num_multiplicities = if lookup_is_allowed {
+        (fixed.total_tables_len as usize).div_ceil(domain_size)
+    } else {
+        0
+    };
+
+    let num_variables = geometry.num_columns_under_copy_permutation + lookup_variables;
+    let num_witnesses = geometry.num_witness_columns;
+    let num_constants = geometry.num_constant_columns + lookup_constants;
+    // copy permutation的连乘按quotient的次数分段，每段之间一个中间结果
+    let num_partial_products = num_variables.div_ceil(quotient_degree) - 1;
+    // 表的每一列加上table id
+    let num_table_columns = if lookup_is_allowed {
+        lookup_width + 1
+    } else {
+        0
+    };
+
+    // witness：变量、witness列、multiplicity；setup：copy permutation的sigma、常量、表
+    let witness_leaf = num_variables + num_witnesses + num_multiplicities;
+    let setup_leaf = num_variables + num_constants + num_table_columns;
+    // stage 2（扩域）：z、中间的连乘、每个子argument和multiplicity的lookup多项式
+    let num_stage_2 = 1 + num_partial_products + num_subarguments + num_multiplicities;
+
+    let (_, num_queries, fri_schedule, final_degree) = compute_fri_schedule(
+        config.security_level as u32,
+        config.merkle_tree_cap_size,
+        config.pow_bits,
+        config.fri_lde_factor.trailing_zeros(),
+        domain_size.trailing_zeros(),
+    );
+
+    Ok(ProofShape {
+        values_at_z: witness_leaf + setup_leaf + num_stage_2 + quotient_degree,
+        values_at_z_omega: 1,
+        values_at_0: num_subarguments + num_multiplicities,
+        witness_leaf,
+        stage_2_leaf: 2 * num_stage_2,
+        quotient_leaf: 2 * quotient_degree,
+        setup_leaf,
+        num_queries,
+        fri_schedule,
+        final_degree,
+    })
+}
+
+// 只检查长度，内容由verifier检查：verifier直接按vk计算出的数量读取proof中的数组
+fn check_shape<D: DemoCircuit>(
+    demo: &D,
+    vk: &VerificationKey<F, H>,
+    proof: &Proof<F, H, GoldilocksExt2>,
+    config: &ProofConfig,
+) -> Result<(), VerifyError> {
+    let malformed = |field: &str| Err(VerifyError::MalformedProof(field.to_string()));
+    let cap_size = config.merkle_tree_cap_size;
+
+    let domain_size = vk.fixed_parameters.domain_size as usize;
+    if !domain_size.is_power_of_two() || domain_size > demo.max_trace_len() {
+        return Err(VerifyError::GeometryMismatch);
+    }
+    let shape = expected_shape(vk, config)?;
+
+    if proof.public_inputs.len() != vk.fixed_parameters.public_inputs_locations.len() {
+        return malformed("public_inputs");
+    }
+
+    let caps = [
+        ("witness_oracle_cap", &proof.witness_oracle_cap),
+        ("stage_2_oracle_cap", &proof.stage_2_oracle_cap),
+        ("quotient_oracle_cap", &proof.quotient_oracle_cap),
+        ("fri_base_oracle_cap", &proof.fri_base_oracle_cap),
+    ];
+    for (name, cap) in caps {
+        if cap.len() != cap_size {
+            return malformed(name);
+        }
+    }
+    if proof.fri_intermediate_oracles_caps.len() + 1 != shape.fri_schedule.len()
+        || proof
+            .fri_intermediate_oracles_caps
+            .iter()
+            .any(|cap| cap.len() != cap_size)
+    {
+        return malformed("fri_intermediate_oracles_caps");
+    }
+    if proof
+        .final_fri_monomials
+        .iter()
+        .any(|monomials| monomials.len() != shape.final_degree)
+    {
+        return malformed("final_fri_monomials");
+    }
+
+    let values = [
+        ("values_at_z", proof.values_at_z.len(), shape.values_at_z),
+        (
+            "values_at_z_omega",
+            proof.values_at_z_omega.len(),
+            shape.values_at_z_omega,
+        ),
+        ("values_at_0", proof.values_at_0.len(), shape.values_at_0),
+    ];
+    for (name, len, expected) in values {
+        if len != expected {
+            return malformed(name);
+        }
+    }
+
+    let repetitions = &proof.queries_per_fri_repetition;
+    if repetitions.len() != shape.num_queries {
+        return malformed("queries_per_fri_repetition");
+    }
+
+    // witness、stage_2、quotient和setup的树都在LDE上：路径长度为log2(domain_size * lde) - log2(cap_size)，
+    // 每折叠一次FRI oracle的树的叶子数就少k比特
+    let lde_bits = (domain_size * config.fri_lde_factor).trailing_zeros() as usize;
+    let cap_bits = cap_size.trailing_zeros() as usize;
+    let path_len = |folded_bits: usize| {
+        lde_bits
+            .checked_sub(folded_bits + cap_bits)
+            .ok_or(VerifyError::GeometryMismatch)
+    };
+    for queries in repetitions {
+        let oracles = [
+            ("witness_query", &queries.witness_query, shape.witness_leaf),
+            ("stage_2_query", &queries.stage_2_query, shape.stage_2_leaf),
+            (
+                "quotient_query",
+                &queries.quotient_query,
+                shape.quotient_leaf,
+            ),
+            ("setup_query", &queries.setup_query, shape.setup_leaf),
+        ];
+        for (name, query, leaf_len) in oracles {
+            if query.leaf_elements.len() != leaf_len || query.proof.len() != path_len(0)? {
+                return malformed(name);
+            }
+        }
+
+        // 每个FRI oracle（base和每个中间层）一次查询：leaf是折叠的2^k个扩域元素
+        if queries.fri_queries.len() != shape.fri_schedule.len() {
+            return malformed("fri_queries");
+        }
+        let mut folded_bits = 0;
+        for (query, fold_bits) in queries.fri_queries.iter().zip(&shape.fri_schedule) {
+            folded_bits += fold_bits;
+            if query.leaf_elements.len() != 2 << fold_bits
+                || query.proof.len() != path_len(folded_bits)?
+            {
+                return malformed("fri_queries");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 调用verifier之前的检查：电路参数、证明参数（proof和vk中的都要与expected一致）以及proof的形状
+// 不一致时CsVerifierBuilder和verifier会panic
+pub fn check_proof<D: DemoCircuit>(
+    demo: &D,
+    vk: &VerificationKey<F, H>,
+    proof: &Proof<F, H, GoldilocksExt2>,
+    expected: &ProofConfig,
+) -> Result<(), VerifyError> {
+    if vk.fixed_parameters.parameters != demo.geometry()
+        || vk.fixed_parameters.lookup_parameters != demo.lookup_parameters()
+    {
+        return Err(VerifyError::GeometryMismatch);
+    }
+    check_config(vk, proof, expected)?;
+    check_shape(demo, vk, proof, expected)
+}
+
+fn verify_demo<D: DemoCircuit>(
+    demo: &D,
+    vk: &VerificationKey<F, H>,
+    proof: &Proof<F, H, GoldilocksExt2>,
+) -> Result<(), VerifyError> {
+    check_proof(demo, vk, proof, &demo_proof_config())?;
+
+    let builder_impl = CsVerifierBuilder::<F, GoldilocksExt2>::new_from_parameters(demo.geometry());
+    let builder = new_builder::<_, F>(builder_impl);
+    let builder = D::configure(builder);
+    let verifier = builder.build(());
+
+    if verifier.verify::<H, TR, NoPow>((), vk, proof) {
+        Ok(())
+    } else {
+        Err(VerifyError::VerificationFailed)
+    }
+}
+
+// 验证通过时返回proof的public input
+pub fn verify(
+    vk_bytes: &[u8],
+    proof_bytes: &[u8],
+    circuit_id: &str,
+) -> Result<Vec<u64>, VerifyError> {
+    let vk: VerificationKey<F, H> = serde_json::from_slice(vk_bytes)
+        .map_err(|err| VerifyError::InvalidVerificationKey(err.to_string()))?;
+    let proof: Proof<F, H, GoldilocksExt2> = serde_json::from_slice(proof_bytes)
+        .map_err(|err| VerifyError::InvalidProof(err.to_string()))?;
+
+    // 电路的配置与参数无关，用默认参数的demo即可
+    match circuit_id {
+        "simple_fibonacci" => verify_demo(&FibonacciDemo::default(), &vk, &proof)?,
+        "prove_verify_fibonacci" => verify_demo(&ConstantFibonacciDemo::default(), &vk, &proof)?,
+        "simple_poly" => verify_demo(&SimplePolyDemo::default(), &vk, &proof)?,
+        "boolean_demo" => verify_demo(&BooleanDemo::default(), &vk, &proof)?,
+        "uint8_demo" => verify_demo(&Uint8Demo::default(), &vk, &proof)?,
+        "lookup_demo" => verify_demo(&LookupDemo::default(), &vk, &proof)?,
+        _ => return Err(VerifyError::UnknownCircuit(circuit_id.to_string())),
+    }
+
+    Ok(proof
+        .public_inputs
+        .iter()
+        .map(|el| el.as_u64_reduced())
+        .collect())
+}
@@ -0,0 +1,296 @@
+// 从放置好的gate恢复的variable连接图（不是synthesize时记录的依赖图）
+//
+// boojum的witness resolver不对外提供记录下的依赖关系，这里只看电路表格中gate实例的variable：
+// - 每个gate实例的最后几个variable（见debugger::GateRelation::outputs）可能由前面的variable计算得到，
+//   例如Fma gate中 d = q * a * b + l * c，所以边是 a, b, c -> gate -> d
+// - 方向是推测的：variable按分配的顺序编号，gadget先分配输入再分配输出。
+//   输出位置上的variable比某个输入分配得早，或者已经是public input、常量、其他gate的输出时，
+//   这个gate只是检查（例如Fibonacci最后的 c * 1 = out），所有variable都是它的输入
+// - 只通过witness resolver（没有gate）计算的依赖不在图中，这样的variable显示为没有来源的witness
+// - PublicInputGate按放置的顺序给出public input
+// 只支持debugger中列出的gate类型；其他类型、specialized列中的gate和lookup记录在unknown_gates中，
+// 这时图是不完整的
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use boojum::{
+    config::DevCSConfig,
+    cs::{implementations::reference_cs::CSReferenceAssembly, LookupParameters, Place},
+    dag::WitnessSource,
+    field::U64Representable,
+};
+
+use super::{debugger::gate_relation, demos::F};
+
+#[derive(Clone, Debug)]
+pub struct GateNode {
+    pub gate: &'static str,
+    pub row: usize,
+    pub instance: usize,
+    pub constants: Vec<F>,
+    pub inputs: Vec<u32>,
+    pub outputs: Vec<u32>,
+    // 只检查已经有来源的variable，没有输出
+    pub check: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WiringGraph {
+    // variable的编号和值
+    pub values: BTreeMap<u32, F>,
+    pub gates: Vec<GateNode>,
+    pub public_inputs: Vec<u32>,
+    pub unknown_gates: Vec<String>,
+}
+
+pub fn build_wiring_graph(cs: &CSReferenceAssembly<F, F, DevCSConfig>) -> WiringGraph {
+    let num_columns = cs.parameters.num_columns_under_copy_permutation;
+    let evaluation_data = &cs.evaluation_data_over_general_purpose_columns;
+
+    let mut graph = WiringGraph::default();
+    for (row, gate_idx) in cs.gates_application_sets.iter().enumerate() {
+        let type_id = evaluation_data.gate_type_ids_for_general_purpose_columns[*gate_idx];
+        let Some(relation) = gate_relation(type_id) else {
+            let name =
+                &evaluation_data.evaluators_over_general_purpose_columns[*gate_idx].debug_name;
+            if !graph.unknown_gates.contains(name) {
+                graph.unknown_gates.push(name.clone());
+            }
+            continue;
+        };
+        if relation.name == "NopGate" {
+            continue;
+        }
+
+        for instance in 0..num_columns / relation.width {
+            let variables: Vec<_> = (instance * relation.width..(instance + 1) * relation.width)
+                .map(|column| cs.copy_permutation_data[column][row])
+                .collect();
+            if variables.iter().any(|variable| variable.is_placeholder()) {
+                continue;
+            }
+
+            let indices: Vec<u32> = variables
+                .iter()
+                .map(|variable| variable.as_variable_index())
+                .collect();
+            for (variable, index) in variables.iter().zip(&indices) {
+                graph
+                    .values
+                    .entry(*index)
+                    .or_insert_with(|| cs.get_value_unchecked(Place::from_variable(*variable)));
+            }
+
+            if relation.name == "PublicInputGate" {
+                graph.public_inputs.push(indices[0]);
+            }
+
+            let (inputs, outputs) = indices.split_at(relation.width - relation.outputs);
+            graph.gates.push(GateNode {
+                gate: relation.name,
+                row,
+                instance,
+                constants: relation
+                    .instance_constants(&cs.constants_requested_per_row[row], instance)
+                    .to_vec(),
+                inputs: inputs.to_vec(),
+                outputs: outputs.to_vec(),
+                check: false,
+            });
+        }
+    }
+
+    mark_checks(&mut graph);
+    add_unknown_columns(cs, &mut graph);
+
+    graph
+}
+
+fn mark_checks(graph: &mut WiringGraph) {
+    let mut defined: BTreeSet<u32> = graph.public_inputs.iter().copied().collect();
+    for gate in &graph.gates {
+        if gate.gate == "ConstantsAllocatorGate" {
+            defined.extend(&gate.outputs);
+        }
+    }
+
+    // 按最晚分配的输入排序：同一个variable出现在多个gate的输出位置时，最早能计算出它的gate是来源
+    // 行的顺序不是synthesize的顺序，gate会被放进之前还有空位的行中
+    let mut order: Vec<usize> = (0..graph.gates.len())
+        .filter(|idx| {
+            let gate = &graph.gates[*idx];
+            gate.gate != "ConstantsAllocatorGate" && !gate.outputs.is_empty()
+        })
+        .collect();
+    order.sort_by_key(|idx| (graph.gates[*idx].inputs.iter().max().copied(), *idx));
+
+    for idx in order {
+        let gate = &mut graph.gates[idx];
+        let allocated_after_inputs = gate
+            .outputs
+            .iter()
+            .all(|output| gate.inputs.iter().all(|input| input < output));
+        if allocated_after_inputs && !gate.outputs.iter().any(|output| defined.contains(output)) {
+            defined.extend(&gate.outputs);
+        } else {
+            let outputs = std::mem::take(&mut gate.outputs);
+            gate.inputs.extend(outputs);
+            gate.check = true;
+        }
+    }
+}
+
+// specialized列中的gate和lookup不在gates_application_sets中
+fn add_unknown_columns(cs: &CSReferenceAssembly<F, F, DevCSConfig>, graph: &mut WiringGraph) {
+    let mut unknown = vec![];
+    for evaluator in &cs
+        .evaluation_data_over_specialized_columns
+        .evaluators_over_specialized_columns
+    {
+        unknown.push(format!("{} (specialized columns)", evaluator.debug_name));
+    }
+    if cs.lookup_parameters != LookupParameters::NoLookup {
+        unknown.push(format!("lookup ({:?})", cs.lookup_parameters));
+    }
+
+    for name in unknown {
+        if !graph.unknown_gates.contains(&name) {
+            graph.unknown_gates.push(name);
+        }
+    }
+}
+
+impl WiringGraph {
+    // 只保留计算这些variable用到的部分
+    pub fn feeding(&self, variables: &[u32]) -> Self {
+        let mut producers: HashMap<u32, Vec<usize>> = HashMap::new();
+        let mut checks: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (gate_idx, gate) in self.gates.iter().enumerate() {
+            for output in &gate.outputs {
+                producers.entry(*output).or_default().push(gate_idx);
+            }
+            if gate.check {
+                for input in &gate.inputs {
+                    checks.entry(*input).or_default().push(gate_idx);
+                }
+            }
+        }
+
+        // 没有来源的witness（例如public input）由检查它的gate约束，这些gate的输入也包括在内
+        let mut visited_variables = BTreeSet::new();
+        let mut visited_gates = BTreeSet::new();
+        let mut stack = variables.to_vec();
+        while let Some(variable) = stack.pop() {
+            if !visited_variables.insert(variable) {
+                continue;
+            }
+            let gates = match producers.get(&variable) {
+                Some(gates) => gates,
+                None => checks.get(&variable).map_or(&[][..], |gates| &gates[..]),
+            };
+            for gate_idx in gates {
+                if visited_gates.insert(*gate_idx) {
+                    stack.extend(&self.gates[*gate_idx].inputs);
+                }
+            }
+        }
+
+        Self {
+            values: self
+                .values
+                .iter()
+                .filter(|(variable, _)| visited_variables.contains(variable))
+                .map(|(variable, value)| (*variable, *value))
+                .collect(),
+            gates: visited_gates
+                .into_iter()
+                .map(|gate_idx| self.gates[gate_idx].clone())
+                .collect(),
+            public_inputs: self
+                .public_inputs
+                .iter()
+                .copied()
+                .filter(|variable| visited_variables.contains(variable))
+                .collect(),
+            unknown_gates: self.unknown_gates.clone(),
+        }
+    }
+
+    pub fn feeding_public_input(&self, index: usize) -> Self {
+        self.feeding(&[self.public_inputs[index]])
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph circuit {\n    rankdir=LR;\n");
+        for (variable, value) in &self.values {
+            let shape = match self
+                .public_inputs
+                .iter()
+                .position(|input| input == variable)
+            {
+                Some(index) => format!("doublecircle, xlabel=\"public input {}\"", index),
+                None => "ellipse".to_string(),
+            };
+            out.push_str(&format!(
+                "    v{} [label=\"v{} = {}\", shape={}];\n",
+                variable,
+                variable,
+                value.as_u64_reduced(),
+                shape
+            ));
+        }
+        for gate in &self.gates {
+            let id = format!("g{}_{}", gate.row, gate.instance);
+            let constants: Vec<String> = gate
+                .constants
+                .iter()
+                .map(|el| el.as_u64_reduced().to_string())
+                .collect();
+            out.push_str(&format!(
+                "    {} [label=\"{}{}\\nrow {} #{}\\n[{}]\", shape=box];\n",
+                id,
+                gate.gate,
+                if gate.check { " (check)" } else { "" },
+                gate.row,
+                gate.instance,
+                constants.join(", ")
+            ));
+            for input in &gate.inputs {
+                out.push_str(&format!("    v{} -> {};\n", input, id));
+            }
+            for output in &gate.outputs {
+                out.push_str(&format!("    {} -> v{};\n", id, output));
+            }
+        }
+        out.push_str("}\n");
+
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let value = |el: &F| el.as_u64_reduced();
+
+        serde_json::json!({
+            "variables": self
+                .values
+                .iter()
+                .map(|(variable, el)| serde_json::json!({ "id": variable, "value": value(el) }))
+                .collect::<Vec<_>>(),
+            "gates": self
+                .gates
+                .iter()
+                .map(|gate| serde_json::json!({
+                    "gate": gate.gate,
+                    "row": gate.row,
+                    "instance": gate.instance,
+                    "constants": gate.constants.iter().map(value).collect::<Vec<_>>(),
+                    "inputs": gate.inputs,
+                    "outputs": gate.outputs,
+                    "check": gate.check,
+                }))
+                .collect::<Vec<_>>(),
+            "public_inputs": self.public_inputs,
+            "unknown_gates": self.unknown_gates,
+        })
+    }
+}
@@ -0,0 +1,225 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use std::cell::RefCell;
+
+use boojum::{
+    cs::{
+        cs_builder::{CsBuilder, CsBuilderImpl},
+        gates::{
+            ConstantAllocatableCS, FmaGateInBaseFieldWithoutConstant,
+            FmaGateInBaseWithoutConstantParams, PublicInputGate,
+        },
+        traits::cs::ConstraintSystem,
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    field::{Field, U64Representable},
+    worker::Worker,
+};
+use common::{
+    debugger::{find_unsatisfied_constraints, ConstraintLabels, ConstraintReport},
+    demos::{
+        fibonacci, visit_all_demos, ConstantFibonacciDemo, DemoCircuit, DemoVisitor, FibonacciDemo,
+        F,
+    },
+    harness::synthesize_assembly,
+    wiring_graph::build_wiring_graph,
+};
+
+// 标签在synthesize时写入，所以在synthesize之后再读取
+fn diagnose<D: DemoCircuit>(
+    demo: &D,
+    labels: Option<&RefCell<ConstraintLabels>>,
+) -> (bool, ConstraintReport) {
+    let worker = Worker::new_with_num_threads(8);
+    let mut cs = synthesize_assembly(demo);
+    let labels = labels.map(|labels| labels.borrow());
+    let report = find_unsatisfied_constraints(&cs, labels.as_deref());
+
+    (cs.check_if_satisfied(&worker), report)
+}
+
+// simple_fibonacci，每一步放在单独标记的一段中，可以指定一步写入错误的witness
+struct LabeledFibonacci {
+    n: usize,
+    wrong_step: Option<usize>,
+    labels: RefCell<ConstraintLabels>,
+}
+
+impl DemoCircuit for LabeledFibonacci {
+    fn name(&self) -> &'static str {
+        "labeled_fibonacci"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        FibonacciDemo::default().geometry()
+    }
+
+    fn max_variables(&self) -> usize {
+        FibonacciDemo::default().max_variables()
+    }
+
+    fn max_trace_len(&self) -> usize {
+        FibonacciDemo::default().max_trace_len()
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        FibonacciDemo::configure(builder)
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let labels = &mut *self.labels.borrow_mut();
+        let one = cs.allocate_constant(F::ONE);
+
+        labels.section(cs, "fibonacci", |cs, labels| {
+            let (mut a_value, mut b_value) = (F::ONE, F::ONE);
+            let mut a = cs.alloc_single_variable_from_witness(a_value);
+            let mut b = cs.alloc_single_variable_from_witness(b_value);
+
+            for step in 0..self.n - 2 {
+                let mut c_value = a_value;
+                c_value.add_assign(&b_value);
+                if self.wrong_step == Some(step) {
+                    c_value.add_assign(&F::ONE);
+                }
+
+                let c = labels.section(cs, &format!("step {}", step), |cs, _| {
+                    // a * 1 + b = c
+                    let c = cs.alloc_single_variable_from_witness(c_value);
+                    let gate = FmaGateInBaseFieldWithoutConstant {
+                        params: FmaGateInBaseWithoutConstantParams {
+                            coeff_for_quadtaric_part: F::ONE,
+                            linear_term_coeff: F::ONE,
+                        },
+                        quadratic_part: (a, one),
+                        linear_part: b,
+                        rhs_part: c,
+                    };
+                    gate.add_to_cs(cs);
+                    c
+                });
+
+                (a, a_value, b, b_value) = (b, b_value, c, c_value);
+            }
+
+            let gate = PublicInputGate::new(b);
+            gate.add_to_cs(cs);
+        });
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![F::from_u64_unchecked(fibonacci(self.n))]
+    }
+}
+
+struct CheckAll;
+
+impl DemoVisitor for CheckAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        let (is_satisfied, report) = diagnose(demo, None);
+        assert!(is_satisfied, "{}", demo.name());
+        assert!(report.is_satisfied(), "{}", demo.name());
+    }
+}
+
+#[test]
+fn demos_have_no_unsatisfied_constraints() {
+    visit_all_demos(&mut CheckAll);
+}
+
+#[test]
+fn wrong_public_output_is_located() {
+    // fibonacci(9) = 34
+    let demo = FibonacciDemo { n: 9, out: 35 };
+    let (is_satisfied, report) = diagnose(&demo, None);
+    assert!(!is_satisfied);
+
+    // 只有最后比较结果的Fma gate不满足：c * 1 + 0 * 1 - out = 34 - 35
+    assert_eq!(report.unsatisfied.len(), 1);
+    let constraint = &report.unsatisfied[0];
+    assert_eq!(constraint.gate, "FmaGateInBaseFieldWithoutConstant");
+    let mut expected = F::ONE;
+    expected.negate();
+    assert_eq!(constraint.value, expected);
+
+    let values: Vec<u64> = constraint
+        .variables
+        .iter()
+        .map(|(_, value)| value.as_u64_reduced())
+        .collect();
+    assert_eq!(values, vec![34, 1, 1, 35]);
+
+    // 第一行是gate和位置，之后每个variable一行
+    let text = constraint.to_string();
+    assert!(text.starts_with(&format!(
+        "FmaGateInBaseFieldWithoutConstant at row {}, instance {}",
+        constraint.row, constraint.instance
+    )));
+    assert_eq!(text.lines().count(), 1 + constraint.variables.len());
+}
+
+#[test]
+fn unsatisfied_constraint_names_its_call_site() {
+    let demo = LabeledFibonacci {
+        n: 9,
+        wrong_step: None,
+        labels: RefCell::new(ConstraintLabels::new()),
+    };
+    let (is_satisfied, report) = diagnose(&demo, Some(&demo.labels));
+    assert!(is_satisfied && report.is_satisfied());
+
+    // 第一步新开了一行
+    let demo = LabeledFibonacci {
+        n: 9,
+        wrong_step: Some(0),
+        labels: RefCell::new(ConstraintLabels::new()),
+    };
+    let (is_satisfied, report) = diagnose(&demo, Some(&demo.labels));
+    assert!(!is_satisfied);
+
+    // 错误的c会传递到后面的witness，但后面的约束仍然成立，只有第一步不满足
+    assert_eq!(report.unsatisfied.len(), 1);
+    let constraint = &report.unsatisfied[0];
+    assert_eq!(constraint.instance, 0);
+    assert_eq!(constraint.columns, 0..4);
+
+    let section = constraint.section.as_ref().expect("没有找到标签");
+    assert_eq!(section.label, "step 0");
+    assert!(section.location.file().ends_with("constraint_debugger.rs"));
+    assert!(constraint
+        .to_string()
+        .ends_with(&format!("placed by \"step 0\" at {}", section.location)));
+}
+
+#[test]
+fn different_constants_in_one_row() {
+    // one和out都由ConstantsAllocatorGate放置，在同一行的不同实例中
+    let demo = ConstantFibonacciDemo::default();
+    let cs = synthesize_assembly(&demo);
+    let graph = build_wiring_graph(&cs);
+    let constants: Vec<_> = graph
+        .gates
+        .iter()
+        .filter(|gate| gate.gate == "ConstantsAllocatorGate")
+        .collect();
+    assert!(constants.iter().any(|gate| {
+        gate.instance > 0
+            && constants.iter().any(|other| {
+                other.row == gate.row && other.instance == 0 && other.constants != gate.constants
+            })
+    }));
+    for gate in &constants {
+        assert_eq!(gate.constants.len(), 1);
+    }
+
+    let (is_satisfied, report) = diagnose(&demo, None);
+    assert!(is_satisfied);
+    assert!(report.is_satisfied(), "{:?}", report.unsatisfied);
+}
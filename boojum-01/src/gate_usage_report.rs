@@ -0,0 +1,130 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use boojum::{
+    cs::{
+        cs_builder::{CsBuilder, CsBuilderImpl},
+        gates::ReductionGate,
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    worker::Worker,
+};
+use common::{
+    demos::{visit_all_demos, DemoCircuit, DemoVisitor, FibonacciDemo, F},
+    gate_report::{gate_report, GateReport},
+    harness::synthesize_assembly,
+};
+
+fn report<D: DemoCircuit>(demo: &D) -> GateReport {
+    let worker = Worker::new_with_num_threads(8);
+    let cs = synthesize_assembly(demo);
+    gate_report(&cs, &worker)
+}
+
+// simple_fibonacci，另外配置了一个没有使用的ReductionGate
+struct FibonacciWithUnusedGate(FibonacciDemo);
+
+impl DemoCircuit for FibonacciWithUnusedGate {
+    fn name(&self) -> &'static str {
+        "fibonacci_with_unused_gate"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        self.0.geometry()
+    }
+
+    fn max_variables(&self) -> usize {
+        self.0.max_variables()
+    }
+
+    fn max_trace_len(&self) -> usize {
+        self.0.max_trace_len()
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = FibonacciDemo::configure(builder);
+        ReductionGate::<F, 4>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        )
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        self.0.synthesize(cs)
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        self.0.public_inputs()
+    }
+}
+
+struct ReportAll;
+
+impl DemoVisitor for ReportAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        let report = report(demo);
+
+        // 每个通用列上的gate都在selector树中
+        for gate in report.gates.iter().filter(|gate| !gate.specialized) {
+            assert!(gate.selector_path.is_some(), "{}", gate.name);
+            assert!(report.selector_tree.contains(&gate.name));
+        }
+        assert!(report.max_degree <= demo.geometry().max_allowed_constraint_degree);
+        assert!(report.quotient_degree.is_power_of_two());
+        assert_eq!(
+            report
+                .gates
+                .iter()
+                .filter(|gate| !gate.specialized)
+                .map(|gate| gate.rows)
+                .sum::<usize>(),
+            report.trace_len
+        );
+    }
+}
+
+#[test]
+fn report_all_demos() {
+    visit_all_demos(&mut ReportAll);
+}
+
+#[test]
+fn fibonacci_gate_usage() {
+    let demo = FibonacciDemo::default();
+    let fibonacci = report(&demo);
+
+    // 8列，每个Fma gate占4列
+    let fma = fibonacci
+        .gates
+        .iter()
+        .find(|gate| gate.instance_width == 4 && gate.rows > 0)
+        .expect("没有找到Fma gate");
+    assert_eq!(fma.instances_per_row, 2);
+    assert_eq!(fma.degree, 2);
+    // n - 2步加上最后的比较，每行两个
+    assert_eq!(fma.rows, (demo.n - 2 + 1 + 1) / 2);
+}
+
+#[test]
+fn unused_and_dominant_gates_are_reported() {
+    let unused = report(&FibonacciWithUnusedGate(FibonacciDemo::default()));
+    assert!(unused
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("never used")));
+
+    // 88个Fma gate占了大部分行
+    let dominant = report(&FibonacciDemo::new(90));
+    assert!(dominant
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("UseSpecializedColumns")));
+}
@@ -0,0 +1,68 @@
+#![feature(allocator_api)]
+
+// 与测试源文件旁边fixtures/golden中保存的vk和proof比较（见common/golden.rs）
+// 电路或者proof格式有意修改之后，用BOOJUM_UPDATE_GOLDEN=1重新生成并提交fixture
+
+mod common;
+
+use common::{
+    demos::{visit_all_demos, DemoCircuit, DemoVisitor, FibonacciDemo},
+    golden::{check_golden, json_diff, prove_deterministic, to_bytes},
+};
+use serde_json::json;
+
+struct CheckAll;
+
+impl DemoVisitor for CheckAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        let check = check_golden(demo);
+        assert!(check.is_unchanged(), "{}", check);
+    }
+}
+
+#[test]
+fn golden_fixtures_are_unchanged() {
+    visit_all_demos(&mut CheckAll);
+}
+
+#[test]
+fn deterministic_proving_is_reproducible() {
+    let demo = FibonacciDemo::default();
+    let (proof, vk) = prove_deterministic(&demo);
+    let (other_proof, other_vk) = prove_deterministic(&demo);
+
+    assert_eq!(to_bytes(&vk), to_bytes(&other_vk));
+    assert_eq!(to_bytes(&proof), to_bytes(&other_proof));
+}
+
+#[test]
+fn vk_diff_names_changed_fields() {
+    let old = json!({
+        "fixed_parameters": { "domain_size": 16, "num_constant_columns": 2 },
+        "setup_merkle_tree_cap": [[1, 2, 3, 4], [5, 6, 7, 8]],
+    });
+    let new = json!({
+        "fixed_parameters": { "domain_size": 32, "num_constant_columns": 2 },
+        "setup_merkle_tree_cap": [[1, 2, 3, 4], [5, 6, 7, 9]],
+    });
+    assert_eq!(
+        json_diff(&old, &new),
+        vec![
+            ".fixed_parameters.domain_size: 16 -> 32".to_string(),
+            ".setup_merkle_tree_cap[1]: [5,6,7,8] -> [5,6,7,9]".to_string(),
+        ]
+    );
+    assert!(json_diff(&old, &old).is_empty());
+
+    // 更长的电路：trace长度和setup都改变了
+    let (_, vk) = prove_deterministic(&FibonacciDemo::default());
+    let (_, longer_vk) = prove_deterministic(&FibonacciDemo::new(90));
+    let lines = json_diff(
+        &serde_json::to_value(&vk).unwrap(),
+        &serde_json::to_value(&longer_vk).unwrap(),
+    );
+    assert!(lines.iter().any(|line| line.contains("domain_size")));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with(".setup_merkle_tree_cap")));
+}
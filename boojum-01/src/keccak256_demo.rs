@@ -0,0 +1,607 @@
+#![feature(allocator_api)]
+use std::alloc::Global;
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidonSponge},
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            ConstantAllocatableCS, ConstantsAllocatorGate, FmaGateInBaseFieldWithoutConstant,
+            FmaGateInBaseWithoutConstantParams, NopGate, PublicInputGate,
+        },
+        implementations::{
+            lookup_table::LookupTable, pow::NoPow, prover::ProofConfig,
+            transcript::GoldilocksPoisedonTranscript,
+        },
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, LookupParameters, StaticToolboxHolder, Variable,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        Field, SmallField, U64Representable,
+    },
+    worker::Worker,
+};
+use derivative::Derivative;
+
+// Keccak-f[1600] 的轮常数
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+// rho步骤的循环移位量，KECCAK_ROTATIONS[x][y]
+const KECCAK_ROTATIONS: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+// Keccak-256 的rate是1088比特，即136字节
+const KECCAK256_RATE: usize = 136;
+
+// 电路外的Keccak-f[1600]，状态按 A[x + 5y] 排列
+fn native_keccak_f(state: &mut [u64; 25]) {
+    for rc in KECCAK_ROUND_CONSTANTS {
+        let c: [u64; 5] = std::array::from_fn(|x| {
+            state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20]
+        });
+        let d: [u64; 5] = std::array::from_fn(|x| c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1));
+        for i in 0..25 {
+            state[i] ^= d[i % 5];
+        }
+
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    state[x + 5 * y].rotate_left(KECCAK_ROTATIONS[x][y]);
+            }
+        }
+
+        for y in 0..5 {
+            for x in 0..5 {
+                state[x + 5 * y] =
+                    b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+
+        state[0] ^= rc;
+    }
+}
+
+// 以太坊使用的是原始Keccak的填充 0x01 ... 0x80，而不是NIST SHA-3的 0x06 ... 0x80
+fn keccak256_pad(message: &[u8]) -> Vec<u8> {
+    let mut padded = message.to_vec();
+    padded.push(0x01);
+    while padded.len() % KECCAK256_RATE != 0 {
+        padded.push(0x00);
+    }
+    // 如果只填充了一个字节，这个字节就是 0x81
+    *padded.last_mut().unwrap() |= 0x80;
+
+    padded
+}
+
+fn native_keccak256(message: &[u8]) -> [u8; 32] {
+    let mut state = [0u64; 25];
+    for block in keccak256_pad(message).chunks_exact(KECCAK256_RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks_exact(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        native_keccak_f(&mut state);
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, lane) in digest.chunks_exact_mut(8).zip(state.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+// 按字节的 xor / andn 查找表：(a, b) -> (a ^ b, !a & b)
+// 查找成功同时说明 a 和 b 都在 [0, 256) 中
+pub const KECCAK_BYTE_OPS_TABLE_NAME: &str = "Keccak byte ops table";
+
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug)]
+pub struct KeccakByteOpsTableMarker;
+
+fn create_byte_ops_table<F: SmallField>() -> LookupTable<F, 4> {
+    let mut all_keys = Vec::with_capacity(1 << 16);
+    for a in 0..256 {
+        for b in 0..256 {
+            let key = smallvec::smallvec![
+                F::from_u64_unchecked(a as u64),
+                F::from_u64_unchecked(b as u64)
+            ];
+            all_keys.push(key);
+        }
+    }
+    LookupTable::new_from_keys_and_generation_function(
+        &all_keys,
+        KECCAK_BYTE_OPS_TABLE_NAME.to_string(),
+        2,
+        |keys| {
+            let a = keys[0].as_u64_reduced() as u8;
+            let b = keys[1].as_u64_reduced() as u8;
+
+            let xor_result = a ^ b;
+            let andn_result = !a & b;
+
+            smallvec::smallvec![
+                F::from_u64_unchecked(xor_result as u64),
+                F::from_u64_unchecked(andn_result as u64)
+            ]
+        },
+    )
+}
+
+// 循环移位查找表：(byte, s) -> ((byte << s) & 0xff, byte >> (8 - s))，s 取 1..8
+// 一个字节左移s位后，低位部分留在本字节，高位部分进入下一个字节
+pub const KECCAK_ROTATION_TABLE_NAME: &str = "Keccak rotation table";
+
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug)]
+pub struct KeccakRotationTableMarker;
+
+fn create_rotation_table<F: SmallField>() -> LookupTable<F, 4> {
+    let mut all_keys = Vec::with_capacity(256 * 7);
+    for byte in 0..256 {
+        for shift in 1..8 {
+            let key = smallvec::smallvec![
+                F::from_u64_unchecked(byte as u64),
+                F::from_u64_unchecked(shift as u64)
+            ];
+            all_keys.push(key);
+        }
+    }
+    LookupTable::new_from_keys_and_generation_function(
+        &all_keys,
+        KECCAK_ROTATION_TABLE_NAME.to_string(),
+        2,
+        |keys| {
+            let byte = keys[0].as_u64_reduced();
+            let shift = keys[1].as_u64_reduced();
+
+            let low_part = (byte << shift) & 0xff;
+            let high_part = byte >> (8 - shift);
+
+            smallvec::smallvec![
+                F::from_u64_unchecked(low_part),
+                F::from_u64_unchecked(high_part)
+            ]
+        },
+    )
+}
+
+// 一个64比特的lane用8个小端字节表示
+type Lane = [Variable; 8];
+
+#[derive(Clone, Copy, Debug)]
+struct KeccakTableIds {
+    byte_ops: u32,
+    rotation: u32,
+}
+
+fn add_keccak_tables<F: SmallField, CS: ConstraintSystem<F>>(cs: &mut CS) -> KeccakTableIds {
+    let byte_ops = cs.add_lookup_table::<KeccakByteOpsTableMarker, 4>(create_byte_ops_table());
+    let rotation = cs.add_lookup_table::<KeccakRotationTableMarker, 4>(create_rotation_table());
+
+    KeccakTableIds { byte_ops, rotation }
+}
+
+fn xor_lanes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: KeccakTableIds,
+    a: &Lane,
+    b: &Lane,
+) -> Lane {
+    std::array::from_fn(|i| {
+        let [xor, _andn] = cs.perform_lookup::<2, 2>(tables.byte_ops, &[a[i], b[i]]);
+        xor
+    })
+}
+
+// !a & b
+fn andn_lanes<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: KeccakTableIds,
+    a: &Lane,
+    b: &Lane,
+) -> Lane {
+    std::array::from_fn(|i| {
+        let [_xor, andn] = cs.perform_lookup::<2, 2>(tables.byte_ops, &[a[i], b[i]]);
+        andn
+    })
+}
+
+// 循环左移：整字节的部分只是重新排列字节，不产生约束；
+// 剩下不足8比特的部分通过查找表拆分，再把相邻字节不相交的两部分相加
+fn rotate_lane_left<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: KeccakTableIds,
+    lane: &Lane,
+    amount: u32,
+) -> Lane {
+    let byte_shift = (amount / 8) as usize;
+    let bit_shift = amount % 8;
+
+    let shifted: Lane = if bit_shift == 0 {
+        *lane
+    } else {
+        let shift = cs.allocate_constant(F::from_u64_unchecked(bit_shift as u64));
+        let one = cs.allocate_constant(F::ONE);
+        let parts: [[Variable; 2]; 8] =
+            std::array::from_fn(|i| cs.perform_lookup::<2, 2>(tables.rotation, &[lane[i], shift]));
+        std::array::from_fn(|i| {
+            let [low_part, _] = parts[i];
+            let [_, high_part] = parts[(i + 7) % 8];
+            // low_part * 1 + high_part
+            FmaGateInBaseFieldWithoutConstant::compute_fma(
+                cs,
+                F::ONE,
+                (low_part, one),
+                F::ONE,
+                high_part,
+            )
+        })
+    };
+
+    std::array::from_fn(|i| shifted[(i + 8 - byte_shift) % 8])
+}
+
+// 电路中的Keccak-f[1600]
+fn keccak_f<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: KeccakTableIds,
+    state: &mut [Lane; 25],
+) {
+    for rc in KECCAK_ROUND_CONSTANTS {
+        // theta
+        let mut c = [state[0], state[1], state[2], state[3], state[4]];
+        for x in 0..5 {
+            for y in 1..5 {
+                c[x] = xor_lanes(cs, tables, &c[x], &state[x + 5 * y]);
+            }
+        }
+        let d: [Lane; 5] = std::array::from_fn(|x| {
+            let rotated = rotate_lane_left(cs, tables, &c[(x + 1) % 5], 1);
+            xor_lanes(cs, tables, &c[(x + 4) % 5], &rotated)
+        });
+        for i in 0..25 {
+            state[i] = xor_lanes(cs, tables, &state[i], &d[i % 5]);
+        }
+
+        // rho 和 pi
+        let mut b = *state;
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    rotate_lane_left(cs, tables, &state[x + 5 * y], KECCAK_ROTATIONS[x][y]);
+            }
+        }
+
+        // chi
+        for y in 0..5 {
+            for x in 0..5 {
+                let t = andn_lanes(cs, tables, &b[(x + 1) % 5 + 5 * y], &b[(x + 2) % 5 + 5 * y]);
+                state[x + 5 * y] = xor_lanes(cs, tables, &b[x + 5 * y], &t);
+            }
+        }
+
+        // iota：只对轮常数中非零的字节做xor
+        for (i, byte) in rc.to_le_bytes().into_iter().enumerate() {
+            if byte != 0 {
+                let constant = cs.allocate_constant(F::from_u64_unchecked(byte as u64));
+                let [xor, _andn] =
+                    cs.perform_lookup::<2, 2>(tables.byte_ops, &[state[0][i], constant]);
+                state[0][i] = xor;
+            }
+        }
+    }
+}
+
+// 电路中的Keccak-256，message中每个字节都会在吸收时经过一次查找，从而完成范围检查
+fn keccak256<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: KeccakTableIds,
+    message: &[Variable],
+) -> [Variable; 32] {
+    // 消息长度在电路构建时确定，填充的字节都是常量
+    let padding = &keccak256_pad(&vec![0u8; message.len()])[message.len()..];
+    let mut padded = message.to_vec();
+    for byte in padding {
+        padded.push(cs.allocate_constant(F::from_u64_unchecked(*byte as u64)));
+    }
+
+    let zero = cs.allocate_constant(F::ZERO);
+    let mut state = [[zero; 8]; 25];
+    for block in padded.chunks_exact(KECCAK256_RATE) {
+        for (j, bytes) in block.chunks_exact(8).enumerate() {
+            let bytes: Lane = bytes.try_into().unwrap();
+            state[j] = xor_lanes(cs, tables, &state[j], &bytes);
+        }
+        keccak_f(cs, tables, &mut state);
+    }
+
+    std::array::from_fn(|i| state[i / 8][i % 8])
+}
+
+// 证明 keccak256(preimage) == claimed_digest，digest的32个字节是public input
+fn synthesize_keccak256<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    tables: KeccakTableIds,
+    preimage: &[u8],
+    claimed_digest: [u8; 32],
+) {
+    let message: Vec<Variable> = preimage
+        .iter()
+        .map(|byte| cs.alloc_single_variable_from_witness(F::from_u64_unchecked(*byte as u64)))
+        .collect();
+
+    let digest = keccak256(cs, tables, &message);
+
+    let one = cs.allocate_constant(F::ONE);
+    let zero = cs.allocate_constant(F::ZERO);
+    for (computed, claimed) in digest.iter().zip(claimed_digest.iter()) {
+        let claimed = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(*claimed as u64));
+        let gate = PublicInputGate::new(claimed);
+        gate.add_to_cs(cs);
+
+        // computed * 1 - claimed == 0
+        let gate = FmaGateInBaseFieldWithoutConstant {
+            params: FmaGateInBaseWithoutConstantParams {
+                coeff_for_quadtaric_part: F::ONE,
+                linear_term_coeff: F::MINUS_ONE,
+            },
+            quadratic_part: (*computed, one),
+            linear_part: claimed,
+            rhs_part: zero,
+        };
+        gate.add_to_cs(cs);
+    }
+}
+
+// 设置电路参数
+const GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 20,
+    num_witness_columns: 0,
+    num_constant_columns: 4,
+    max_allowed_constraint_degree: 4,
+};
+
+const MAX_VARIABLES: usize = 1 << 20; // variable数量上限
+const MAX_TRACE_LEN: usize = 1 << 17; // 电路表格的行数上限，要能放下两张查找表（65536 + 1792行）
+
+// 配置cs的函数
+fn configure<
+    F: SmallField,
+    T: CsBuilderImpl<F, T>,
+    GC: GateConfigurationHolder<F>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    // 两张表都是2个key、2个value
+    let builder = builder.allow_lookup(
+        LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+            width: 4,
+            num_repetitions: 8,
+            share_table_id: true,
+        },
+    );
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+fn bytes_from_hex<const N: usize>(hex: &str) -> [u8; N] {
+    std::array::from_fn(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+}
+
+// 以太坊中常见的Keccak-256结果
+const ETHEREUM_VECTORS: [(&str, &str); 4] = [
+    (
+        "",
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+    ),
+    (
+        "abc",
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+    ),
+    // ERC20 transfer 的函数选择器 0xa9059cbb
+    (
+        "transfer(address,uint256)",
+        "a9059cbb2ab09eb219583f4a59a5d0623ade346d962bcd4e46b11da047c9049b",
+    ),
+    // ERC20 Transfer 事件的topic0
+    (
+        "Transfer(address,address,uint256)",
+        "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+    ),
+];
+
+// keccak256(abi.encodePacked(address, uint256))
+const ADDRESS: &str = "d8da6bf26964af9d7eed9e03e53415d37aa96045";
+const VALUE: u128 = 1_000_000_000_000_000_000; // 1 ether
+const ADDRESS_VALUE_DIGEST: &str =
+    "898b28abc3b318d281ab44bf71a700c763700a6f080ac541176f4eba7be15d44";
+
+fn address_value_preimage() -> Vec<u8> {
+    let address: [u8; 20] = bytes_from_hex(ADDRESS);
+    let mut value = [0u8; 32];
+    value[16..].copy_from_slice(&VALUE.to_be_bytes());
+
+    let mut preimage = address.to_vec();
+    preimage.extend_from_slice(&value);
+    preimage
+}
+
+fn keccak256_is_satisfied(preimage: &[u8], claimed_digest: [u8; 32]) -> bool {
+    type P = GoldilocksField;
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    let tables = add_keccak_tables(&mut cs);
+    synthesize_keccak256(&mut cs, tables, preimage, claimed_digest);
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(8);
+    let mut cs = cs.into_assembly::<Global>();
+    cs.check_if_satisfied(&worker)
+}
+
+#[test]
+fn native_keccak256_vectors() {
+    for (message, expected) in ETHEREUM_VECTORS {
+        assert_eq!(
+            native_keccak256(message.as_bytes()),
+            bytes_from_hex(expected)
+        );
+    }
+    assert_eq!(
+        native_keccak256(&address_value_preimage()),
+        bytes_from_hex(ADDRESS_VALUE_DIGEST)
+    );
+}
+
+#[test]
+fn keccak256_matches_native() {
+    for (message, expected) in ETHEREUM_VECTORS {
+        assert!(keccak256_is_satisfied(
+            message.as_bytes(),
+            bytes_from_hex(expected)
+        ));
+    }
+
+    // rate边界：135字节只填充一个0x81，136字节需要再吸收一个完整的填充块
+    for len in [135, 136] {
+        let message: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        let digest = native_keccak256(&message);
+        assert!(keccak256_is_satisfied(&message, digest));
+
+        let mut wrong_digest = digest;
+        wrong_digest[31] ^= 1;
+        assert!(keccak256_is_satisfied(&message, wrong_digest) == false);
+    }
+}
+
+#[test]
+fn keccak256_address_value_demo() {
+    type P = GoldilocksField;
+
+    let preimage = address_value_preimage();
+    let digest: [u8; 32] = bytes_from_hex(ADDRESS_VALUE_DIGEST);
+
+    // cs builder: 约束系统的工厂类
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    let tables = add_keccak_tables(&mut cs);
+    synthesize_keccak256(&mut cs, tables, &preimage, digest);
+
+    // optional
+    cs.pad_and_shrink();
+
+    // 设置线程数量
+    let worker = Worker::new_with_num_threads(8);
+    let cs = cs.into_assembly::<Global>();
+
+    // FRI 的 LDE 因子
+    let lde_factor_to_use = 16;
+    let proof_config = ProofConfig {
+        fri_lde_factor: lde_factor_to_use,
+        pow_bits: 0,
+        merkle_tree_cap_size: 16,
+        ..Default::default()
+    };
+
+    // 使用prove_one_shot同时生成proof和vk，在生产环境不建议这样使用
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    // verifier只看到摘要的32个字节
+    let public_digest: Vec<GoldilocksField> = digest
+        .iter()
+        .map(|byte| GoldilocksField::from_u64_unchecked(*byte as u64))
+        .collect();
+    assert_eq!(proof.public_inputs, public_digest);
+
+    let builder_impl =
+        CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(GEOMETRY);
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
@@ -0,0 +1,427 @@
+#![feature(allocator_api)]
+use std::alloc::Global;
+
+use boojum::{
+    algebraic_props::{
+        round_function::AbsorptionModeOverwrite,
+        sponge::{GoldilocksPoseidon2Sponge, GoldilocksPoseidonSponge},
+    },
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            BooleanConstraintGate, ConstantsAllocatorGate, FmaGateInBaseFieldWithoutConstant,
+            NopGate, Poseidon2FlattenedGate, PublicInputGate, SelectionGate,
+        },
+        implementations::{
+            pow::NoPow, prover::ProofConfig, transcript::GoldilocksPoisedonTranscript,
+        },
+        oracle::TreeHasher,
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        U64Representable,
+    },
+    gadgets::{
+        boolean::Boolean,
+        num::Num,
+        recursion::recursive_tree_hasher::{CircuitGoldilocksPoseidon2Sponge, CircuitTreeHasher},
+        traits::selectable::Selectable,
+    },
+    implementations::poseidon2::Poseidon2Goldilocks,
+    worker::Worker,
+};
+
+type F = GoldilocksField;
+type Digest = [F; 4];
+
+// 电路外和电路内使用同一个哈希：Poseidon2 sponge，输出 4 个 Goldilocks 元素
+fn hash_elements(elements: &[F]) -> Digest {
+    <GoldilocksPoseidon2Sponge<AbsorptionModeOverwrite> as TreeHasher<F>>::hash_into_leaf(elements)
+}
+
+// 内部节点的哈希：把所有子节点的摘要按顺序拼接后哈希
+// 二叉树时正好是一个rate（8个元素），四叉树时是两个rate
+fn hash_children(children: &[Digest]) -> Digest {
+    let elements: Vec<F> = children.iter().flatten().copied().collect();
+    hash_elements(&elements)
+}
+
+// 每一层中，当前节点在兄弟节点中的位置由 log2(arity) 个比特给出，低位在前
+// 电路中用条件交换把当前节点放到正确的位置：
+//   第一个比特决定当前节点与 siblings[0] 组成的一对中谁在前；
+//   第二个比特（只有四叉树有）决定这一对与 (siblings[1], siblings[2]) 谁在前。
+// 因此位置为 p 的节点的兄弟顺序是 [c[p ^ 1], c[(p ^ 2) & !1], c[((p ^ 2) & !1) + 1]]
+fn sibling_positions(arity: usize, position: usize) -> Vec<usize> {
+    match arity {
+        2 => vec![position ^ 1],
+        4 => {
+            let other_pair = (position ^ 2) & !1;
+            vec![position ^ 1, other_pair, other_pair + 1]
+        }
+        _ => panic!("只支持二叉树和四叉树"),
+    }
+}
+
+fn index_bits(arity: usize, position: usize) -> Vec<bool> {
+    (0..arity.trailing_zeros())
+        .map(|i| (position >> i) & 1 == 1)
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+struct MerklePath {
+    // 每层 arity - 1 个兄弟节点
+    siblings: Vec<Vec<Digest>>,
+    // 每层 log2(arity) 个比特，低位在前
+    index_bits: Vec<Vec<bool>>,
+}
+
+// 电路外的Merkle树，用于生成root和路径
+#[derive(Clone, Debug)]
+struct MerkleTree {
+    arity: usize,
+    // layers[0] 是叶子的哈希，最后一层只有root
+    layers: Vec<Vec<Digest>>,
+}
+
+impl MerkleTree {
+    // 叶子数量不足 arity^depth 时用全零摘要补齐
+    fn new(leaves: &[Vec<F>], arity: usize, depth: usize) -> Self {
+        assert!(arity == 2 || arity == 4, "只支持二叉树和四叉树");
+        let capacity = arity.pow(depth as u32);
+        assert!(leaves.len() <= capacity, "叶子数量超过了树的容量");
+
+        let mut layer: Vec<Digest> = leaves.iter().map(|leaf| hash_elements(leaf)).collect();
+        layer.resize(capacity, [F::from_u64_unchecked(0); 4]);
+
+        let mut layers = vec![layer];
+        for _ in 0..depth {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(arity)
+                .map(hash_children)
+                .collect();
+            layers.push(next);
+        }
+
+        Self { arity, layers }
+    }
+
+    fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    fn root(&self) -> Digest {
+        self.layers.last().unwrap()[0]
+    }
+
+    fn path(&self, index: usize) -> MerklePath {
+        let mut siblings = Vec::with_capacity(self.depth());
+        let mut bits = Vec::with_capacity(self.depth());
+
+        let mut index = index;
+        for layer in self.layers[..self.depth()].iter() {
+            let group = &layer[index - index % self.arity..][..self.arity];
+            let position = index % self.arity;
+
+            siblings.push(
+                sibling_positions(self.arity, position)
+                    .into_iter()
+                    .map(|p| group[p])
+                    .collect(),
+            );
+            bits.push(index_bits(self.arity, position));
+
+            index /= self.arity;
+        }
+
+        MerklePath {
+            siblings,
+            index_bits: bits,
+        }
+    }
+}
+
+// 按照电路中同样的规则，在电路外沿路径计算root
+fn root_from_path(leaf: &[F], path: &MerklePath) -> Digest {
+    let mut current = hash_elements(leaf);
+    for (siblings, bits) in path.siblings.iter().zip(path.index_bits.iter()) {
+        let mut first_pair = [current, siblings[0]];
+        if bits[0] {
+            first_pair.swap(0, 1);
+        }
+        let children = if bits.len() == 1 {
+            first_pair.to_vec()
+        } else if bits[1] {
+            vec![siblings[1], siblings[2], first_pair[0], first_pair[1]]
+        } else {
+            vec![first_pair[0], first_pair[1], siblings[1], siblings[2]]
+        };
+        current = hash_children(&children);
+    }
+
+    current
+}
+
+// 条件交换：flag 为真时返回 (b, a)，否则返回 (a, b)
+fn conditional_swap<CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    flag: Boolean<F>,
+    a: &[Num<F>; 4],
+    b: &[Num<F>; 4],
+) -> ([Num<F>; 4], [Num<F>; 4]) {
+    let first = std::array::from_fn(|i| Num::conditionally_select(cs, flag, &b[i], &a[i]));
+    let second = std::array::from_fn(|i| Num::conditionally_select(cs, flag, &a[i], &b[i]));
+
+    (first, second)
+}
+
+fn circuit_hash_children<CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    children: &[[Num<F>; 4]],
+) -> [Num<F>; 4] {
+    let elements: Vec<Num<F>> = children.iter().flatten().copied().collect();
+    CircuitGoldilocksPoseidon2Sponge::hash_into_leaf(cs, elements.iter())
+}
+
+// Merkle包含证明：leaf 和路径是witness，root 是public input
+fn synthesize_membership<CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    leaf: &[F],
+    path: &MerklePath,
+    root: Digest,
+) {
+    let leaf: Vec<Num<F>> = leaf.iter().map(|x| Num::allocate(cs, *x)).collect();
+    let mut current = CircuitGoldilocksPoseidon2Sponge::hash_into_leaf(cs, leaf.iter());
+
+    for (siblings, bits) in path.siblings.iter().zip(path.index_bits.iter()) {
+        let siblings: Vec<[Num<F>; 4]> = siblings
+            .iter()
+            .map(|s| s.map(|x| Num::allocate(cs, x)))
+            .collect();
+        // Boolean::allocate 会放置一个 BooleanConstraintGate
+        let bits: Vec<Boolean<F>> = bits.iter().map(|b| Boolean::allocate(cs, *b)).collect();
+
+        let (left, right) = conditional_swap(cs, bits[0], &current, &siblings[0]);
+        let children = if bits.len() == 1 {
+            vec![left, right]
+        } else {
+            let (first, second) = conditional_swap(cs, bits[1], &left, &siblings[1]);
+            let (first_right, second_right) = conditional_swap(cs, bits[1], &right, &siblings[2]);
+            vec![first, first_right, second, second_right]
+        };
+
+        current = circuit_hash_children(cs, &children);
+    }
+
+    for (computed, expected) in current.iter().zip(root.iter()) {
+        let expected = Num::allocate(cs, *expected);
+        let gate = PublicInputGate::new(expected.get_variable());
+        gate.add_to_cs(cs);
+
+        Num::enforce_equal(cs, computed, &expected);
+    }
+}
+
+// 设置电路参数
+const GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 20,
+    num_witness_columns: 0,
+    num_constant_columns: 4,
+    max_allowed_constraint_degree: 8,
+};
+
+const MAX_VARIABLES: usize = 1 << 16; // variable数量上限
+const MAX_TRACE_LEN: usize = 1 << 12; // 电路表格的行数上限
+
+// 配置cs的函数
+fn configure<T: CsBuilderImpl<F, T>, GC: GateConfigurationHolder<F>, TB: StaticToolboxHolder>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 约束索引比特为布尔值
+    let builder = BooleanConstraintGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 条件交换
+    let builder =
+        SelectionGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = Poseidon2FlattenedGate::<F, 8, 12, 4, Poseidon2Goldilocks>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseSpecializedColumns {
+            num_repetitions: 1,
+            share_constants: false,
+        },
+    );
+    // 在cs中加入空操作门，用于pad_and_shrink
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+fn test_leaves(num_leaves: usize) -> Vec<Vec<F>> {
+    (0..num_leaves)
+        .map(|i| {
+            vec![
+                F::from_u64_unchecked(i as u64),
+                F::from_u64_unchecked(0xdead_beef ^ i as u64),
+            ]
+        })
+        .collect()
+}
+
+fn membership_is_satisfied(leaf: &[F], path: &MerklePath, root: Digest) -> bool {
+    type P = GoldilocksField;
+
+    let builder_impl =
+        CsReferenceImplementationBuilder::<F, P, DevCSConfig>::new(GEOMETRY, MAX_TRACE_LEN);
+    let builder = new_builder::<_, F>(builder_impl);
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    synthesize_membership(&mut cs, leaf, path, root);
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(1);
+    let mut cs = cs.into_assembly::<Global>();
+    cs.check_if_satisfied(&worker)
+}
+
+#[test]
+fn native_merkle_paths() {
+    for (arity, depth) in [(2, 1), (2, 5), (4, 1), (4, 3)] {
+        let leaves = test_leaves(arity.pow(depth as u32) - 1);
+        let tree = MerkleTree::new(&leaves, arity, depth);
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(index);
+            assert_eq!(path.siblings.len(), depth);
+            assert_eq!(root_from_path(leaf, &path), tree.root());
+        }
+    }
+}
+
+#[test]
+fn merkle_membership_matches_native() {
+    for (arity, depth) in [(2, 4), (4, 2)] {
+        let leaves = test_leaves(arity.pow(depth as u32));
+        let tree = MerkleTree::new(&leaves, arity, depth);
+
+        for index in [0, 1, leaves.len() / 2 + 1, leaves.len() - 1] {
+            let path = tree.path(index);
+            assert!(membership_is_satisfied(&leaves[index], &path, tree.root()));
+        }
+    }
+}
+
+#[test]
+fn merkle_membership_rejects_bad_paths() {
+    for (arity, depth) in [(2, 4), (4, 2)] {
+        let leaves = test_leaves(arity.pow(depth as u32));
+        let tree = MerkleTree::new(&leaves, arity, depth);
+        let index = 5;
+        let path = tree.path(index);
+
+        // 错误的兄弟节点
+        let mut wrong_sibling = path.clone();
+        wrong_sibling.siblings[1][0][2] =
+            F::from_u64_unchecked(wrong_sibling.siblings[1][0][2].as_u64_reduced() ^ 1);
+        assert!(membership_is_satisfied(&leaves[index], &wrong_sibling, tree.root()) == false);
+
+        // 翻转一个索引比特
+        let mut flipped_bit = path.clone();
+        flipped_bit.index_bits[0][0] = !flipped_bit.index_bits[0][0];
+        assert!(membership_is_satisfied(&leaves[index], &flipped_bit, tree.root()) == false);
+
+        // 不在树中的叶子
+        let not_a_leaf = vec![F::from_u64_unchecked(12345)];
+        assert!(membership_is_satisfied(&not_a_leaf, &path, tree.root()) == false);
+    }
+}
+
+#[test]
+fn merkle_membership_demo() {
+    type P = GoldilocksField;
+
+    // 深度为 8 的二叉树，例如红包的领取名单
+    let leaves = test_leaves(200);
+    let tree = MerkleTree::new(&leaves, 2, 8);
+    let index = 123;
+    let path = tree.path(index);
+
+    // cs builder: 约束系统的工厂类
+    let builder_impl =
+        CsReferenceImplementationBuilder::<F, P, DevCSConfig>::new(GEOMETRY, MAX_TRACE_LEN);
+    let builder = new_builder::<_, F>(builder_impl);
+
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    synthesize_membership(&mut cs, &leaves[index], &path, tree.root());
+
+    // optional
+    cs.pad_and_shrink();
+
+    // 设置线程数量
+    let worker = Worker::new_with_num_threads(1);
+    let cs = cs.into_assembly::<Global>();
+
+    // FRI 的 LDE 因子
+    let lde_factor_to_use = 16;
+    let proof_config = ProofConfig {
+        fri_lde_factor: lde_factor_to_use,
+        pow_bits: 0,
+        merkle_tree_cap_size: 4,
+        ..Default::default()
+    };
+
+    // 使用prove_one_shot同时生成proof和vk，在生产环境不建议这样使用
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    // verifier只看到root
+    assert_eq!(proof.public_inputs, tree.root().to_vec());
+
+    let builder_impl = CsVerifierBuilder::<F, GoldilocksExt2>::new_from_parameters(GEOMETRY);
+    let builder = new_builder::<_, F>(builder_impl);
+
+    let builder = configure(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
@@ -0,0 +1,220 @@
+#![feature(allocator_api)]
+use std::alloc::Global;
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidon2Sponge},
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            ConstantsAllocatorGate, FmaGateInBaseFieldWithoutConstant, NopGate,
+            Poseidon2FlattenedGate, PublicInputGate,
+        },
+        implementations::{
+            pow::NoPow, prover::ProofConfig, transcript::GoldilocksPoisedonTranscript,
+        },
+        oracle::TreeHasher,
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        U64Representable,
+    },
+    gadgets::{
+        num::Num,
+        recursion::recursive_tree_hasher::{CircuitGoldilocksPoseidon2Sponge, CircuitTreeHasher},
+    },
+    implementations::poseidon2::Poseidon2Goldilocks,
+    worker::Worker,
+};
+
+// prover构建Merkle树时使用的sponge，电路外的摘要也用它计算
+type H = GoldilocksPoseidon2Sponge<AbsorptionModeOverwrite>;
+
+fn native_digest(preimage: &[GoldilocksField]) -> [GoldilocksField; 4] {
+    <H as TreeHasher<GoldilocksField>>::hash_into_leaf(preimage)
+}
+
+// 证明知道 preimage，使得 Poseidon2(preimage) == claimed_digest
+// preimage 是witness，claimed_digest 是public input
+fn synthesize_preimage_knowledge<CS: ConstraintSystem<GoldilocksField>>(
+    cs: &mut CS,
+    preimage: &[GoldilocksField],
+    claimed_digest: [GoldilocksField; 4],
+) {
+    let preimage: Vec<Num<GoldilocksField>> =
+        preimage.iter().map(|x| Num::allocate(cs, *x)).collect();
+
+    // 在电路中计算哈希，每次置换都会放置一个Poseidon2FlattenedGate
+    let digest = CircuitGoldilocksPoseidon2Sponge::hash_into_leaf(cs, preimage.iter());
+
+    for (computed, claimed) in digest.iter().zip(claimed_digest.iter()) {
+        let claimed = Num::allocate(cs, *claimed);
+        let gate = PublicInputGate::new(claimed.get_variable());
+        gate.add_to_cs(cs);
+
+        Num::enforce_equal(cs, computed, &claimed);
+    }
+}
+
+// 设置电路参数
+const GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 20,
+    num_witness_columns: 0,
+    num_constant_columns: 4,
+    max_allowed_constraint_degree: 8,
+};
+
+const MAX_VARIABLES: usize = 1 << 14; // variable数量上限
+const MAX_TRACE_LEN: usize = 1 << 10; // 电路表格的行数上限
+
+// 配置cs的函数
+// Poseidon2FlattenedGate只能用于Goldilocks，所以这里不对域做泛型
+fn configure<
+    T: CsBuilderImpl<GoldilocksField, T>,
+    GC: GateConfigurationHolder<GoldilocksField>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, GoldilocksField, GC, TB>,
+) -> CsBuilder<
+    T,
+    GoldilocksField,
+    impl GateConfigurationHolder<GoldilocksField>,
+    impl StaticToolboxHolder,
+> {
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // 一次Poseidon2置换（宽度12，rate 8，capacity 4）对应一个门
+    // 放在专用列中：这些列只放这一种门，不需要selector，也不会提高其它门的次数
+    let builder =
+        Poseidon2FlattenedGate::<GoldilocksField, 8, 12, 4, Poseidon2Goldilocks>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseSpecializedColumns {
+                num_repetitions: 1,
+                share_constants: false,
+            },
+        );
+    // 在cs中加入空操作门，用于pad_and_shrink
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+fn test_preimage(len: usize) -> Vec<GoldilocksField> {
+    (0..len)
+        .map(|i| GoldilocksField::from_u64_unchecked(0x1234_5678 * (i as u64 + 1)))
+        .collect()
+}
+
+// 电路是否满足，用于和电路外的哈希对照
+fn preimage_is_satisfied(
+    preimage: &[GoldilocksField],
+    claimed_digest: [GoldilocksField; 4],
+) -> bool {
+    type P = GoldilocksField;
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    synthesize_preimage_knowledge(&mut cs, preimage, claimed_digest);
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(1);
+    let mut cs = cs.into_assembly::<Global>();
+    cs.check_if_satisfied(&worker)
+}
+
+#[test]
+fn poseidon2_digest_matches_native() {
+    // 覆盖不足一个rate、正好一个rate和多个rate的情况
+    for len in [1, 7, 8, 9, 16, 25] {
+        let preimage = test_preimage(len);
+        let digest = native_digest(&preimage);
+        assert!(preimage_is_satisfied(&preimage, digest));
+
+        // 错误的摘要不能满足约束
+        let mut wrong_digest = digest;
+        wrong_digest[len % 4] =
+            GoldilocksField::from_u64_unchecked(wrong_digest[len % 4].as_u64_reduced() ^ 1);
+        assert!(preimage_is_satisfied(&preimage, wrong_digest) == false);
+    }
+}
+
+#[test]
+fn poseidon2_preimage_demo() {
+    type P = GoldilocksField;
+
+    let preimage = test_preimage(10);
+    let digest = native_digest(&preimage);
+
+    // cs builder: 约束系统的工厂类
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    synthesize_preimage_knowledge(&mut cs, &preimage, digest);
+
+    // optional
+    cs.pad_and_shrink();
+
+    // 设置线程数量
+    let worker = Worker::new_with_num_threads(1);
+    let cs = cs.into_assembly::<Global>();
+
+    // FRI 的 LDE 因子
+    let lde_factor_to_use = 16;
+    let proof_config = ProofConfig {
+        fri_lde_factor: lde_factor_to_use,
+        pow_bits: 0,
+        merkle_tree_cap_size: 4,
+        ..Default::default()
+    };
+
+    // Merkle树使用与native_digest相同的Poseidon2 sponge
+    // 使用prove_one_shot同时生成proof和vk，在生产环境不建议这样使用
+    let (proof, vk) = cs.prove_one_shot::<GoldilocksExt2, GoldilocksPoisedonTranscript, H, NoPow>(
+        &worker,
+        proof_config,
+        (),
+    );
+
+    // verifier只看到摘要，看不到preimage
+    assert_eq!(proof.public_inputs, digest.to_vec());
+
+    let builder_impl =
+        CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(GEOMETRY);
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<H, GoldilocksPoisedonTranscript, NoPow>((), &vk, &proof);
+
+    assert!(is_valid);
+}
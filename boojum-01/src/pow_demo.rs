@@ -0,0 +1,124 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use std::time::Instant;
+
+use blake2::Blake2s256;
+use boojum::{
+    algebraic_props::{
+        round_function::AbsorptionModeOverwrite,
+        sponge::{GoldilocksPoseidon2Sponge, GoldilocksPoseidonSponge},
+    },
+    cs::implementations::{
+        pow::PoWRunner, prover::ProofConfig, transcript::GoldilocksPoisedonTranscript,
+    },
+    field::{goldilocks::GoldilocksField, U64Representable},
+    worker::Worker,
+};
+use common::{
+    demos::{FibonacciDemo, SimplePolyDemo},
+    harness::{prove, prove_and_verify, verify, HashConfiguration, PowConfiguration, ProofProfile},
+};
+
+type PoseidonPow = GoldilocksPoseidon2Sponge<AbsorptionModeOverwrite>;
+type TreeHasher = GoldilocksPoseidonSponge<AbsorptionModeOverwrite>;
+
+fn grinding_config(pow_bits: u32) -> ProofConfig {
+    ProofConfig {
+        pow_bits,
+        ..ProofProfile::Demo.proof_config()
+    }
+}
+
+#[test]
+fn pow_proofs_are_accepted() {
+    let worker = Worker::new_with_num_threads(8);
+    let demo = FibonacciDemo::default();
+
+    for hash in [HashConfiguration::Poseidon, HashConfiguration::Blake2s] {
+        for pow in [PowConfiguration::Blake2s, PowConfiguration::Poseidon] {
+            let report = prove_and_verify(&demo, hash, pow, &worker, grinding_config(12));
+            assert!(report.is_valid, "{:?} {:?}", hash, pow);
+        }
+    }
+
+    // Production配置默认开启grinding
+    let config = ProofProfile::Production.proof_config();
+    assert!(config.pow_bits > 0);
+    let report = prove_and_verify(
+        &SimplePolyDemo::default(),
+        HashConfiguration::Poseidon,
+        PowConfiguration::Blake2s,
+        &worker,
+        config,
+    );
+    assert!(report.is_valid);
+}
+
+fn wrong_nonce_is_rejected<POW: PoWRunner>() {
+    let worker = Worker::new_with_num_threads(8);
+    let demo = FibonacciDemo::default();
+
+    let (proof, vk) = prove::<_, GoldilocksPoisedonTranscript, TreeHasher, POW>(
+        &demo,
+        &worker,
+        grinding_config(12),
+    );
+    assert!(verify::<_, GoldilocksPoisedonTranscript, TreeHasher, POW>(
+        &demo, &vk, &proof
+    ));
+
+    // 任何其它nonce都会改变之后的FRI查询位置，而且几乎不可能满足难度要求
+    for delta in [1, 2, 1 << 40] {
+        let mut tampered = proof.clone();
+        tampered.pow_challenge ^= delta;
+        assert!(
+            verify::<_, GoldilocksPoisedonTranscript, TreeHasher, POW>(&demo, &vk, &tampered)
+                == false
+        );
+    }
+}
+
+#[test]
+fn blake2s_pow_wrong_nonce_is_rejected() {
+    wrong_nonce_is_rejected::<Blake2s256>();
+}
+
+#[test]
+fn poseidon_pow_wrong_nonce_is_rejected() {
+    wrong_nonce_is_rejected::<PoseidonPow>();
+}
+
+fn grinding_benchmark<POW: PoWRunner>(name: &str, worker: &Worker) {
+    let seed: Vec<GoldilocksField> = (0..4)
+        .map(|i| GoldilocksField::from_u64_unchecked(0x1234_5678 + i))
+        .collect();
+
+    for pow_bits in [8, 12, 16, 20] {
+        let start = Instant::now();
+        let nonce = POW::run_from_field_elements(seed.clone(), pow_bits, worker);
+        let grinding_time = start.elapsed();
+
+        assert!(POW::verify_from_field_elements(
+            seed.clone(),
+            pow_bits,
+            nonce
+        ));
+        println!(
+            "{:<10} {:>4} bits {:>12?} nonce {}",
+            name, pow_bits, grinding_time, nonce
+        );
+    }
+}
+
+// grinding的期望时间随pow_bits指数增长，每多1比特大约翻倍
+// 性能测试，默认不运行：cargo test --test pow_demo -- --ignored --nocapture
+#[test]
+#[ignore]
+fn pow_grinding_benchmark() {
+    let worker = Worker::new_with_num_threads(8);
+
+    grinding_benchmark::<Blake2s256>("blake2s", &worker);
+    grinding_benchmark::<PoseidonPow>("poseidon2", &worker);
+}
@@ -0,0 +1,77 @@
+#![feature(allocator_api)]
+
+// 每个阶段的时间、内存和Worker利用率（见common/profiling.rs）
+// 生成的Chrome trace写到临时目录，可以用chrome://tracing或者Perfetto打开
+
+mod common;
+
+use boojum::{cs::implementations::pow::NoPow, worker::Worker};
+use common::{
+    demos::{visit_all_demos, DemoCircuit, DemoVisitor, FibonacciDemo},
+    harness::{prove_in, verify, ProofProfile},
+    profiling::{profile, ProfileReport, TrackingAllocator},
+    recursion::{H, TR},
+};
+
+const NUM_THREADS: usize = 8;
+
+fn profile_demo<D: DemoCircuit>(demo: &D) -> ProfileReport {
+    let worker = Worker::new_with_num_threads(NUM_THREADS);
+    let (is_valid, report) = profile(NUM_THREADS, || {
+        let (proof, vk) = prove_in::<D, TR, H, NoPow, TrackingAllocator>(
+            demo,
+            &worker,
+            ProofProfile::Demo.proof_config(),
+        );
+        verify::<D, TR, H, NoPow>(demo, &vk, &proof)
+    });
+    assert!(is_valid, "{}", demo.name());
+
+    let path = std::env::temp_dir().join(format!("{}_trace.json", demo.name()));
+    std::fs::write(
+        &path,
+        serde_json::to_string_pretty(&report.to_chrome_trace()).unwrap(),
+    )
+    .unwrap();
+
+    report
+}
+
+struct ProfileAll;
+
+impl DemoVisitor for ProfileAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        profile_demo(demo);
+    }
+}
+
+#[test]
+fn profile_all_demos() {
+    visit_all_demos(&mut ProfileAll);
+}
+
+#[test]
+fn fibonacci_stages() {
+    let report = profile_demo(&FibonacciDemo::default());
+
+    let names: Vec<&str> = report.stages.iter().map(|stage| stage.name).collect();
+    assert_eq!(
+        names,
+        vec!["synthesize", "into_assembly", "setup", "prove", "verify"]
+    );
+
+    // setup和prove中的多项式使用TrackingAllocator
+    assert!(report.stage("setup").unwrap().peak_bytes > 0);
+    assert!(report.stage("prove").unwrap().peak_bytes > 0);
+    for stage in &report.stages {
+        if let Some(utilization) = stage.utilization(report.num_threads) {
+            assert!(utilization >= 0.0);
+        }
+    }
+
+    let trace = report.to_chrome_trace();
+    let events = trace["traceEvents"].as_array().unwrap();
+    assert_eq!(events.len(), report.stages.len());
+    assert_eq!(events[3]["name"], "prove");
+    assert_eq!(events[3]["ph"], "X");
+}
@@ -0,0 +1,282 @@
+#![feature(allocator_api)]
+
+// 随机参数的demo电路的属性测试，每个用例检查：
+// - 正确的witness可以生成并通过验证的proof
+// - 声称错误的结果时check_if_satisfied不通过
+// - proof的大小（calldata的word数）在参数范围两端的proof大小之间
+//
+// 默认使用ProofProfile::Fast（lde 8）和较少的用例，适合在CI上运行；
+// 设置BOOJUM_PROPTEST_FULL=1时使用ProofProfile::Demo并增加用例数
+
+mod common;
+
+use std::sync::OnceLock;
+
+use boojum::{
+    cs::{
+        cs_builder::{CsBuilder, CsBuilderImpl},
+        gates::{
+            ConstantAllocatableCS, ConstantsAllocatorGate, FmaGateInBaseFieldWithoutConstant,
+            NopGate, PublicInputGate, ReductionGate, ReductionGateParams,
+        },
+        implementations::{pow::NoPow, prover::ProofConfig},
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    field::{Field, U64Representable},
+    worker::Worker,
+};
+use common::{
+    calldata::encode_proof,
+    demos::{ConstantFibonacciDemo, DemoCircuit, FibonacciDemo, LookupDemo, Uint8Demo, F},
+    harness::{prove, synthesize_assembly, verify, ProofProfile},
+    recursion::{H, TR},
+};
+use proptest::{prelude::*, test_runner::TestCaseError};
+
+const MAX_FIBONACCI_N: usize = 60;
+const MAX_LOOKUP_REPETITIONS: usize = 101;
+// add_no_overflow要求x + x不超过8比特
+const MAX_UINT8_X: u8 = 127;
+
+fn full_mode() -> bool {
+    std::env::var("BOOJUM_PROPTEST_FULL").is_ok()
+}
+
+fn proof_config() -> ProofConfig {
+    if full_mode() {
+        ProofProfile::Demo.proof_config()
+    } else {
+        ProofProfile::Fast.proof_config()
+    }
+}
+
+fn config() -> ProptestConfig {
+    ProptestConfig::with_cases(if full_mode() { 64 } else { 8 })
+}
+
+fn proof_size<D: DemoCircuit>(demo: &D) -> usize {
+    let worker = Worker::new_with_num_threads(8);
+    let (proof, _) = prove::<D, TR, H, NoPow>(demo, &worker, proof_config());
+    encode_proof(&proof).len()
+}
+
+fn check_case<D: DemoCircuit>(
+    honest: &D,
+    wrong: &D,
+    bounds: (usize, usize),
+) -> Result<(), TestCaseError> {
+    let worker = Worker::new_with_num_threads(8);
+
+    let (proof, vk) = prove::<D, TR, H, NoPow>(honest, &worker, proof_config());
+    prop_assert!(verify::<D, TR, H, NoPow>(honest, &vk, &proof));
+    prop_assert_eq!(&proof.public_inputs, &honest.public_inputs());
+
+    let size = encode_proof(&proof).len();
+    prop_assert!(
+        bounds.0 <= size && size <= bounds.1,
+        "proof size {} not in {:?}",
+        size,
+        bounds
+    );
+
+    let mut cs = synthesize_assembly(wrong);
+    prop_assert!(!cs.check_if_satisfied(&worker));
+
+    Ok(())
+}
+
+// c3 * x^3 + c2 * x^2 + c1 * x + c0 = out，out是public input
+#[derive(Clone, Debug)]
+struct PolyDemo {
+    coeffs: [u64; 4],
+    x: u64,
+    out: F,
+}
+
+impl PolyDemo {
+    fn new(coeffs: [u64; 4], x: u64) -> Self {
+        let x_el = F::from_u64_with_reduction(x);
+        let mut out = F::ZERO;
+        for coeff in coeffs {
+            out.mul_assign(&x_el)
+                .add_assign(&F::from_u64_with_reduction(coeff));
+        }
+
+        Self { coeffs, x, out }
+    }
+}
+
+impl DemoCircuit for PolyDemo {
+    fn name(&self) -> &'static str {
+        "random_poly"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        CSGeometry {
+            num_columns_under_copy_permutation: 8,
+            num_witness_columns: 0,
+            num_constant_columns: 8,
+            max_allowed_constraint_degree: 8,
+        }
+    }
+
+    fn max_variables(&self) -> usize {
+        512
+    }
+
+    fn max_trace_len(&self) -> usize {
+        128
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = PublicInputGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder = ReductionGate::<F, 4>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+        builder
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let one = cs.allocate_constant(F::ONE);
+
+        let out = cs.alloc_single_variable_from_witness(self.out);
+        let gate = PublicInputGate::new(out);
+        gate.add_to_cs(cs);
+
+        let x = cs.alloc_single_variable_from_witness(F::from_u64_with_reduction(self.x));
+        let x_square =
+            FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (x, x), F::ZERO, one);
+        let x_cube =
+            FmaGateInBaseFieldWithoutConstant::compute_fma(cs, F::ONE, (x_square, x), F::ZERO, one);
+
+        // 系数作为gate的常量
+        let gate = ReductionGate {
+            params: ReductionGateParams {
+                reduction_constants: self.coeffs.map(F::from_u64_with_reduction),
+            },
+            terms: [x_cube, x_square, x, one],
+            reduction_result: out,
+        };
+        gate.add_to_cs(cs);
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        vec![self.out]
+    }
+}
+
+// x + (x + x) + (x - x) + x * x
+fn uint8_demo(x: u8) -> Uint8Demo {
+    let value = x as u64;
+    Uint8Demo {
+        x,
+        expected: value + (value + value) + value * value,
+    }
+}
+
+static FIBONACCI_BOUNDS: OnceLock<(usize, usize)> = OnceLock::new();
+static CONSTANT_FIBONACCI_BOUNDS: OnceLock<(usize, usize)> = OnceLock::new();
+static POLY_BOUNDS: OnceLock<(usize, usize)> = OnceLock::new();
+static LOOKUP_BOUNDS: OnceLock<(usize, usize)> = OnceLock::new();
+static UINT8_BOUNDS: OnceLock<(usize, usize)> = OnceLock::new();
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn fibonacci_properties(n in 3..=MAX_FIBONACCI_N, delta in 1u64..1000) {
+        let bounds = *FIBONACCI_BOUNDS.get_or_init(|| {
+            (
+                proof_size(&FibonacciDemo::new(3)),
+                proof_size(&FibonacciDemo::new(MAX_FIBONACCI_N)),
+            )
+        });
+        let honest = FibonacciDemo::new(n);
+        let wrong = FibonacciDemo { n, out: honest.out + delta };
+        check_case(&honest, &wrong, bounds)?;
+    }
+
+    #[test]
+    fn constant_fibonacci_properties(n in 3..=MAX_FIBONACCI_N, delta in 1u64..1000) {
+        let bounds = *CONSTANT_FIBONACCI_BOUNDS.get_or_init(|| {
+            (
+                proof_size(&ConstantFibonacciDemo::new(3)),
+                proof_size(&ConstantFibonacciDemo::new(MAX_FIBONACCI_N)),
+            )
+        });
+        let honest = ConstantFibonacciDemo::new(n);
+        let wrong = ConstantFibonacciDemo { n, out: honest.out + delta };
+        check_case(&honest, &wrong, bounds)?;
+    }
+
+    #[test]
+    fn poly_properties(coeffs in any::<[u64; 4]>(), x in any::<u64>(), delta in 1u64..1000) {
+        // 电路的形状与系数和x无关
+        let bounds = *POLY_BOUNDS.get_or_init(|| {
+            let size = proof_size(&PolyDemo::new([1, 0, 1, 5], 3));
+            (size, size)
+        });
+        let honest = PolyDemo::new(coeffs, x);
+        let mut wrong = honest.clone();
+        wrong.out.add_assign(&F::from_u64_unchecked(delta));
+        check_case(&honest, &wrong, bounds)?;
+    }
+
+    #[test]
+    fn lookup_properties(
+        a in 0u64..8,
+        b in 0u64..8,
+        repetitions in 1..=MAX_LOOKUP_REPETITIONS,
+        wrong_expected in 0u64..8,
+    ) {
+        prop_assume!(wrong_expected != a ^ b);
+        let bounds = *LOOKUP_BOUNDS.get_or_init(|| {
+            (
+                proof_size(&LookupDemo::new(1, 2, 1)),
+                proof_size(&LookupDemo::new(1, 2, MAX_LOOKUP_REPETITIONS)),
+            )
+        });
+        let honest = LookupDemo::new(a, b, repetitions);
+        let wrong = LookupDemo {
+            expected: wrong_expected,
+            ..honest.clone()
+        };
+        check_case(&honest, &wrong, bounds)?;
+    }
+
+    #[test]
+    fn uint8_properties(x in 0..=MAX_UINT8_X, delta in 1u64..1000) {
+        let bounds = *UINT8_BOUNDS.get_or_init(|| {
+            let size = proof_size(&uint8_demo(1));
+            (size, size)
+        });
+        let honest = uint8_demo(x);
+        let wrong = Uint8Demo {
+            expected: honest.expected + delta,
+            ..honest.clone()
+        };
+        check_case(&honest, &wrong, bounds)?;
+    }
+}
@@ -0,0 +1,138 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use boojum::{
+    cs::{
+        cs_builder::{CsBuilder, CsBuilderImpl},
+        gates::PublicInputGate,
+        implementations::{pow::NoPow, proof::Proof, verifier::VerificationKey},
+        traits::cs::ConstraintSystem,
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    field::U64Representable,
+    worker::Worker,
+};
+use common::{
+    demos::{DemoCircuit, FibonacciDemo, F},
+    harness::{prove, synthesize_assembly, verify, ProofProfile},
+    recursion::{
+        configure_recursion, verify_in_circuit, EXT, H, RECURSION_GEOMETRY,
+        RECURSION_MAX_TRACE_LEN, RECURSION_MAX_VARIABLES, TR,
+    },
+};
+
+// 在电路中验证一个simple_fibonacci的proof，并把它的public input（out）作为外层电路的public input
+struct RecursiveFibonacci {
+    inner: FibonacciDemo,
+    vk: VerificationKey<F, H>,
+    proof: Proof<F, H, EXT>,
+}
+
+impl DemoCircuit for RecursiveFibonacci {
+    fn name(&self) -> &'static str {
+        "recursive_fibonacci"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        RECURSION_GEOMETRY
+    }
+
+    fn max_variables(&self) -> usize {
+        RECURSION_MAX_VARIABLES
+    }
+
+    fn max_trace_len(&self) -> usize {
+        RECURSION_MAX_TRACE_LEN
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        configure_recursion(builder)
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        let public_inputs = verify_in_circuit::<FibonacciDemo, CS>(
+            cs,
+            self.inner.geometry(),
+            &self.vk,
+            &self.proof,
+        );
+
+        for public_input in public_inputs {
+            let gate = PublicInputGate::new(public_input.get_variable());
+            gate.add_to_cs(cs);
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        self.proof.public_inputs.clone()
+    }
+}
+
+impl RecursiveFibonacci {
+    fn new() -> Self {
+        let worker = Worker::new_with_num_threads(8);
+        let inner = FibonacciDemo::default();
+        let (proof, vk) =
+            prove::<_, TR, H, NoPow>(&inner, &worker, ProofProfile::Demo.proof_config());
+
+        Self { inner, vk, proof }
+    }
+
+    // 同一个vk，换一个proof
+    fn with_proof(&self, proof: Proof<F, H, EXT>) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            vk: self.vk.clone(),
+            proof,
+        }
+    }
+}
+
+fn recursion_is_satisfied(circuit: &RecursiveFibonacci) -> bool {
+    let worker = Worker::new_with_num_threads(8);
+    let mut cs = synthesize_assembly(circuit);
+    cs.check_if_satisfied(&worker)
+}
+
+#[test]
+fn recursive_verifier_rejects_invalid_proofs() {
+    let circuit = RecursiveFibonacci::new();
+    assert!(recursion_is_satisfied(&circuit));
+
+    // 修改public input：out = 35
+    let mut tampered = circuit.proof.clone();
+    tampered.public_inputs[0] = F::from_u64_unchecked(35);
+    assert!(recursion_is_satisfied(&circuit.with_proof(tampered)) == false);
+
+    // 修改一次FRI查询中打开的值
+    let mut tampered = circuit.proof.clone();
+    let leaf = &mut tampered.queries_per_fri_repetition[0]
+        .witness_query
+        .leaf_elements[0];
+    *leaf = F::from_u64_unchecked(leaf.as_u64_reduced() ^ 1);
+    assert!(recursion_is_satisfied(&circuit.with_proof(tampered)) == false);
+}
+
+#[test]
+fn recursive_fibonacci_demo() {
+    // 内层：simple_fibonacci，out = 34
+    let circuit = RecursiveFibonacci::new();
+
+    // 外层：证明"我验证了一个out = 34的simple_fibonacci proof"
+    let worker = Worker::new_with_num_threads(8);
+    let (outer_proof, outer_vk) =
+        prove::<_, TR, H, NoPow>(&circuit, &worker, ProofProfile::Demo.proof_config());
+
+    // 外层proof的public input就是内层的out
+    assert_eq!(outer_proof.public_inputs, vec![F::from_u64_unchecked(34)]);
+
+    // 外层proof可以直接用native verifier验证
+    assert!(verify::<_, TR, H, NoPow>(&circuit, &outer_vk, &outer_proof));
+}
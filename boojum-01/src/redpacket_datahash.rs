@@ -0,0 +1,664 @@
+#![feature(allocator_api)]
+use std::{alloc::Global, sync::Arc};
+
+use boojum::{
+    algebraic_props::{
+        round_function::AbsorptionModeOverwrite,
+        sponge::{GoldilocksPoseidon2Sponge, GoldilocksPoseidonSponge},
+    },
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            BooleanConstraintGate, ConstantsAllocatorGate, DotProductGate,
+            FmaGateInBaseFieldWithoutConstant, NopGate, Poseidon2FlattenedGate, PublicInputGate,
+            ReductionGate, SelectionGate, UIntXAddGate, ZeroCheckGate,
+        },
+        implementations::{
+            pow::NoPow, prover::ProofConfig, transcript::GoldilocksPoisedonTranscript,
+        },
+        oracle::TreeHasher,
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, LookupParameters, StaticToolboxHolder,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        SmallField, U64Representable,
+    },
+    gadgets::{
+        non_native_field::{
+            implementations::{NonNativeFieldOverU16, NonNativeFieldOverU16Params},
+            traits::NonNativeField,
+        },
+        num::Num,
+        recursion::recursive_tree_hasher::{CircuitGoldilocksPoseidon2Sponge, CircuitTreeHasher},
+        tables::{create_range_check_16_bits_table, RangeCheck16BitsTable},
+    },
+    implementations::poseidon2::Poseidon2Goldilocks,
+    pairing::{
+        bn256::Fr,
+        ff::{Field as _, PrimeField},
+    },
+    worker::Worker,
+};
+
+// Lesson05/zksync-redpacket/circuits/datahash.circom 中的 PoseidonHasher 的 Boojum 版本
+//
+// circom 版本在 BN254 的标量域上计算 Poseidon(1)，用 Groth16 证明。这里给出两个版本：
+// 1. 用 Goldilocks 上原生的 Poseidon2，电路很小，但摘要与 circom 不同；
+// 2. 用非原生域运算在 Goldilocks 电路中模拟 BN254 上的 circomlib Poseidon，摘要与 circom 逐位相同。
+
+// circomlib Poseidon(1) 的参数：t = 2，R_F = 8，R_P = 56，S-box 为 x^5
+// 由 Poseidon 参考实现的 Grain LFSR 生成（field = 1, sbox = 0, n = 254），与 circomlibjs 的 poseidon_constants 一致
+const POSEIDON_T2_ROUND_CONSTANTS: [&str; 128] = [
+    "4417881134626180770308697923359573201005643519861877412381846989312604493735",
+    "5433650512959517612316327474713065966758808864213826738576266661723522780033",
+    "13641176377184356099764086973022553863760045607496549923679278773208775739952",
+    "17949713444224994136330421782109149544629237834775211751417461773584374506783",
+    "13765628375339178273710281891027109699578766420463125835325926111705201856003",
+    "19179513468172002314585757290678967643352171735526887944518845346318719730387",
+    "5157412437176756884543472904098424903141745259452875378101256928559722612176",
+    "535160875740282236955320458485730000677124519901643397458212725410971557409",
+    "1050793453380762984940163090920066886770841063557081906093018330633089036729",
+    "10665495010329663932664894101216428400933984666065399374198502106997623173873",
+    "19965634623406616956648724894636666805991993496469370618546874926025059150737",
+    "13007250030070838431593222885902415182312449212965120303174723305710127422213",
+    "16877538715074991604507979123743768693428157847423939051086744213162455276374",
+    "18211747749504876135588847560312685184956239426147543810126553367063157141465",
+    "18151553319826126919739798892854572062191241985315767086020821632812331245635",
+    "19957033149976712666746140949846950406660099037474791840946955175819555930825",
+    "3469514863538261843186854830917934449567467100548474599735384052339577040841",
+    "989698510043911779243192466312362856042600749099921773896924315611668507708",
+    "12568377015646290945235387813564567111330046038050864455358059568128000172201",
+    "20856104135605479600325529349246932565148587186338606236677138505306779314172",
+    "8206918720503535523121349917159924938835810381723474192155637697065780938424",
+    "1309058477013932989380617265069188723120054926187607548493110334522527703566",
+    "14076116939332667074621703729512195584105250395163383769419390236426287710606",
+    "10153498892749751942204288991871286290442690932856658983589258153608012428674",
+    "18202499207234128286137597834010475797175973146805180988367589376893530181575",
+    "12739388830157083522877690211447248168864006284243907142044329113461613743052",
+    "15123358710467780770838026754240340042441262572309759635224051333176022613949",
+    "19925004701844594370904593774447343836015483888496504201331110250494635362184",
+    "10352416606816998476681131583320899030072315953910679608943150613208329645891",
+    "10567371822366244361703342347428230537114808440249611395507235283708966113221",
+    "5635498582763880627392290206431559361272660937399944184533035305989295959602",
+    "11866432933224219174041051738704352719163271639958083608224676028593315904909",
+    "5795020705294401441272215064554385591292330721703923167136157291459784140431",
+    "9482202378699252817564375087302794636287866584767523335624368774856230692758",
+    "4245237636894546151746468406560945873445548423466753843402086544922216329298",
+    "12000500941313982757584712677991730019124834399479314697467598397927435905133",
+    "7596790274058425558167520209857956363736666939016807569082239187494363541787",
+    "2484867918246116343205467273440098378820186751202461278013576281097918148877",
+    "18312645949449997391810445935615409295369169383463185688973803378104013950190",
+    "15320686572748723004980855263301182130424010735782762814513954166519592552733",
+    "12618438900597948888520621062416758747872180395546164387827245287017031303859",
+    "17438141672027706116733201008397064011774368832458707512367404736905021019585",
+    "6374197807230665998865688675365359100400438034755781666913068586172586548950",
+    "2189398913433273865510950346186699930188746169476472274335177556702504595264",
+    "6268495580028970231803791523870131137294646402347399003576649137450213034606",
+    "17896250365994900261202920044129628104272791547990619503076839618914047059275",
+    "13692156312448722528008862371944543449350293305158722920787736248435893008873",
+    "15234446864368744483209945022439268713300180233589581910497691316744177619376",
+    "1572426502623310766593681563281600503979671244997798691029595521622402217227",
+    "80103447810215150918585162168214870083573048458555897999822831203653996617",
+    "8228820324013669567851850635126713973797711779951230446503353812192849106342",
+    "5375851433746509614045812476958526065449377558695752132494533666370449415873",
+    "12115998939203497346386774317892338270561208357481805380546938146796257365018",
+    "9764067909645821279940531410531154041386008396840887338272986634350423466622",
+    "8538708244538850542384936174629541085495830544298260335345008245230827876882",
+    "7140127896620013355910287215441004676619168261422440177712039790284719613114",
+    "14297402962228458726038826185823085337698917275385741292940049024977027409762",
+    "6667115556431351074165934212337261254608231545257434281887966406956835140819",
+    "20226761165244293291042617464655196752671169026542832236139342122602741090001",
+    "12038289506489256655759141386763477208196694421666339040483042079632134429119",
+    "19027757334170818571203982241812412991528769934917288000224335655934473717551",
+    "16272152964456553579565580463468069884359929612321610357528838696790370074720",
+    "2500392889689246014710135696485946334448570271481948765283016105301740284071",
+    "8595254970528530312401637448610398388203855633951264114100575485022581946023",
+    "11635945688914011450976408058407206367914559009113158286982919675551688078198",
+    "614739068603482619581328040478536306925147663946742687395148680260956671871",
+    "18692271780377861570175282183255720350972693125537599213951106550953176268753",
+    "4987059230784976306647166378298632695585915319042844495357753339378260807164",
+    "21851403978498723616722415377430107676258664746210815234490134600998983955497",
+    "9830635451186415300891533983087800047564037813328875992115573428596207326204",
+    "4842706106434537116860242620706030229206345167233200482994958847436425185478",
+    "6422235064906823218421386871122109085799298052314922856340127798647926126490",
+    "4564364104986856861943331689105797031330091877115997069096365671501473357846",
+    "1944043894089780613038197112872830569538541856657037469098448708685350671343",
+    "21179865974855950600518216085229498748425990426231530451599322283119880194955",
+    "14296697761894107574369608843560006996183955751502547883167824879840894933162",
+    "12274619649702218570450581712439138337725246879938860735460378251639845671898",
+    "16371396450276899401411886674029075408418848209575273031725505038938314070356",
+    "3702561221750983937578095019779188631407216522704543451228773892695044653565",
+    "19721616877735564664624984774636557499099875603996426215495516594530838681980",
+    "6383350109027696789969911008057747025018308755462287526819231672217685282429",
+    "20860583956177367265984596617324237471765572961978977333122281041544719622905",
+    "5766390934595026947545001478457407504285452477687752470140790011329357286275",
+    "4043175758319898049344746138515323336207420888499903387536875603879441092484",
+    "15579382179133608217098622223834161692266188678101563820988612253342538956534",
+    "1864640783252634743892105383926602930909039567065240010338908865509831749824",
+    "15943719865023133586707144161652035291705809358178262514871056013754142625673",
+    "2326415993032390211558498780803238091925402878871059708106213703504162832999",
+    "19995326402773833553207196590622808505547443523750970375738981396588337910289",
+    "5143583711361588952673350526320181330406047695593201009385718506918735286622",
+    "15436006486881920976813738625999473183944244531070780793506388892313517319583",
+    "16660446760173633166698660166238066533278664023818938868110282615200613695857",
+    "4966065365695755376133119391352131079892396024584848298231004326013366253934",
+    "20683781957411705574951987677641476019618457561419278856689645563561076926702",
+    "17280836839165902792086432296371645107551519324565649849400948918605456875699",
+    "17045635513701208892073056357048619435743564064921155892004135325530808465371",
+    "17055032967194400710390142791334572297458033582458169295920670679093585707295",
+    "15727174639569115300068198908071514334002742825679221638729902577962862163505",
+    "1001755657610446661315902885492677747789366510875120894840818704741370398633",
+    "18638547332826171619311285502376343504539399518545103511265465604926625041234",
+    "6751954224763196429755298529194402870632445298969935050224267844020826420799",
+    "3526747115904224771452549517614107688674036840088422555827581348280834879405",
+    "15705897908180497062880001271426561999724005008972544196300715293701537574122",
+    "574386695213920937259007343820417029802510752426579750428758189312416867750",
+    "15973040855000600860816974646787367136127946402908768408978806375685439868553",
+    "20934130413948796333037139460875996342810005558806621330680156931816867321122",
+    "6918585327145564636398173845411579411526758237572034236476079610890705810764",
+    "14158163500813182062258176233162498241310167509137716527054939926126453647182",
+    "4164602626597695668474100217150111342272610479949122406544277384862187287433",
+    "12146526846507496913615390662823936206892812880963914267275606265272996025304",
+    "10153527926900017763244212043512822363696541810586522108597162891799345289938",
+    "13564663485965299104296214940873270349072051793008946663855767889066202733588",
+    "5612449256997576125867742696783020582952387615430650198777254717398552960096",
+    "12151885480032032868507892738683067544172874895736290365318623681886999930120",
+    "380452237704664384810613424095477896605414037288009963200982915188629772177",
+    "9067557551252570188533509616805287919563636482030947363841198066124642069518",
+    "21280306817619711661335268484199763923870315733198162896599997188206277056900",
+    "5567165819557297006750252582140767993422097822227408837378089569369734876257",
+    "10411936321072105429908396649383171465939606386380071222095155850987201580137",
+    "21338390051413922944780864872652000187403217966653363270851298678606449622266",
+    "12156296560457833712186127325312904760045212412680904475497938949653569234473",
+    "4271647814574748734312113971565139132510281260328947438246615707172526380757",
+    "9061738206062369647211128232833114177054715885442782773131292534862178874950",
+    "10134551893627587797380445583959894183158393780166496661696555422178052339133",
+    "8932270237664043612366044102088319242789325050842783721780970129656616386103",
+    "3339412934966886386194449782756711637636784424032779155216609410591712750636",
+    "9704903972004596791086522314847373103670545861209569267884026709445485704400",
+    "17467570179597572575614276429760169990940929887711661192333523245667228809456",
+];
+
+const POSEIDON_T2_MDS: [[&str; 2]; 2] = [
+    [
+        "2910766817845651019878574839501801340070030115151021261302834310722729507541",
+        "19727366863391167538122140361473584127147630672623100827934084310230022599144",
+    ],
+    [
+        "5776684794125549462448597414050232243778680302179439492664047328281728356345",
+        "8348174920934122550483593999453880006756108121341067172388445916328941978568",
+    ],
+];
+
+const POSEIDON_T2_FULL_ROUNDS: usize = 8;
+const POSEIDON_T2_PARTIAL_ROUNDS: usize = 56;
+
+// BN254 的标量域元素用 17 个 16 位的limb表示
+type Bn254Fr<F> = NonNativeFieldOverU16<F, Fr, 17>;
+
+fn fr_from_decimal(s: &str) -> Fr {
+    Fr::from_str(s).expect("不能解析BN254域元素")
+}
+
+fn poseidon_t2_params() -> (Vec<Fr>, [[Fr; 2]; 2]) {
+    let round_constants = POSEIDON_T2_ROUND_CONSTANTS
+        .iter()
+        .map(|c| fr_from_decimal(c))
+        .collect();
+    let mds = POSEIDON_T2_MDS.map(|row| row.map(fr_from_decimal));
+
+    (round_constants, mds)
+}
+
+fn is_full_round(round: usize) -> bool {
+    round < POSEIDON_T2_FULL_ROUNDS / 2
+        || round >= POSEIDON_T2_FULL_ROUNDS / 2 + POSEIDON_T2_PARTIAL_ROUNDS
+}
+
+// 电路外的 circomlib Poseidon(1)，与 circomlibjs 的 poseidon([input]) 相同
+// 初始状态为 [0, input]，每轮依次加轮常量、S-box、乘 MDS 矩阵，输出 state[0]
+fn circomlib_poseidon1(input: Fr) -> Fr {
+    let (round_constants, mds) = poseidon_t2_params();
+
+    let pow5 = |x: Fr| {
+        let mut result = x;
+        result.square();
+        result.square();
+        result.mul_assign(&x);
+        result
+    };
+
+    let mut state = [Fr::zero(), input];
+    for round in 0..POSEIDON_T2_FULL_ROUNDS + POSEIDON_T2_PARTIAL_ROUNDS {
+        for (i, s) in state.iter_mut().enumerate() {
+            s.add_assign(&round_constants[round * 2 + i]);
+        }
+
+        if is_full_round(round) {
+            state = state.map(pow5);
+        } else {
+            state[0] = pow5(state[0]);
+        }
+
+        let mut new_state = [Fr::zero(); 2];
+        for (i, row) in mds.iter().enumerate() {
+            for (m, s) in row.iter().zip(state.iter()) {
+                let mut term = *m;
+                term.mul_assign(s);
+                new_state[i].add_assign(&term);
+            }
+        }
+        state = new_state;
+    }
+
+    state[0]
+}
+
+// 在电路中用非原生域运算计算 circomlib Poseidon(1)
+fn synthesize_circomlib_poseidon1<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    input: Fr,
+    params: &Arc<NonNativeFieldOverU16Params<Fr, 17>>,
+) -> Bn254Fr<F> {
+    let (round_constants, mds) = poseidon_t2_params();
+
+    let mut mds = mds.map(|row| row.map(|m| Bn254Fr::allocated_constant(cs, m, params)));
+
+    let sbox = |cs: &mut CS, x: &mut Bn254Fr<F>| {
+        let mut x2 = x.square(cs);
+        let mut x4 = x2.square(cs);
+        x4.mul(cs, x)
+    };
+
+    let mut state = [
+        Bn254Fr::allocated_constant(cs, Fr::zero(), params),
+        Bn254Fr::allocate_checked(cs, input, params),
+    ];
+
+    for round in 0..POSEIDON_T2_FULL_ROUNDS + POSEIDON_T2_PARTIAL_ROUNDS {
+        for (i, s) in state.iter_mut().enumerate() {
+            let mut c = Bn254Fr::allocated_constant(cs, round_constants[round * 2 + i], params);
+            *s = s.add(cs, &mut c);
+        }
+
+        if is_full_round(round) {
+            for s in state.iter_mut() {
+                *s = sbox(cs, s);
+            }
+        } else {
+            state[0] = sbox(cs, &mut state[0]);
+        }
+
+        // new_state[i] = mds[i][0] * state[0] + mds[i][1] * state[1]
+        state = mds.each_mut().map(|row| {
+            let mut t0 = row[0].mul(cs, &mut state[0]);
+            let mut t1 = row[1].mul(cs, &mut state[1]);
+            let mut sum = t0.add(cs, &mut t1);
+            sum.normalize(cs);
+            sum
+        });
+    }
+
+    let [mut out, _] = state;
+    out.normalize(cs);
+
+    out
+}
+
+// 把输出的 16 个 16 位limb（共256位）设为public input，verifier可以据此还原出 circom 的输出
+fn expose_bn254_output<F: SmallField, CS: ConstraintSystem<F>>(cs: &mut CS, out: &Bn254Fr<F>) {
+    for limb in out.limbs[..16].iter() {
+        let gate = PublicInputGate::new(*limb);
+        gate.add_to_cs(cs);
+    }
+}
+
+fn fr_to_u16_limbs(value: Fr) -> Vec<u64> {
+    let repr = value.into_repr();
+    repr.as_ref()
+        .iter()
+        .flat_map(|word| (0..4).map(move |i| (word >> (16 * i)) & 0xffff))
+        .collect()
+}
+
+// Goldilocks 版本：对一个 Goldilocks 元素做 Poseidon2，输出 4 个元素
+fn native_goldilocks_datahash(input: GoldilocksField) -> [GoldilocksField; 4] {
+    <GoldilocksPoseidon2Sponge<AbsorptionModeOverwrite> as TreeHasher<GoldilocksField>>::hash_into_leaf(
+        &[input],
+    )
+}
+
+fn synthesize_goldilocks_datahash<CS: ConstraintSystem<GoldilocksField>>(
+    cs: &mut CS,
+    input: GoldilocksField,
+) -> [Num<GoldilocksField>; 4] {
+    let input = Num::allocate(cs, input);
+    let digest = CircuitGoldilocksPoseidon2Sponge::hash_into_leaf(cs, [input].iter());
+    for x in digest.iter() {
+        let gate = PublicInputGate::new(x.get_variable());
+        gate.add_to_cs(cs);
+    }
+
+    digest
+}
+
+// Goldilocks 版本的电路参数
+const GOLDILOCKS_GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 20,
+    num_witness_columns: 0,
+    num_constant_columns: 4,
+    max_allowed_constraint_degree: 8,
+};
+
+// 只需要常量、public input 和 Poseidon2 门
+fn configure_goldilocks<
+    T: CsBuilderImpl<GoldilocksField, T>,
+    GC: GateConfigurationHolder<GoldilocksField>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, GoldilocksField, GC, TB>,
+) -> CsBuilder<
+    T,
+    GoldilocksField,
+    impl GateConfigurationHolder<GoldilocksField>,
+    impl StaticToolboxHolder,
+> {
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        Poseidon2FlattenedGate::<GoldilocksField, 8, 12, 4, Poseidon2Goldilocks>::configure_builder(
+            builder,
+            GatePlacementStrategy::UseSpecializedColumns {
+                num_repetitions: 1,
+                share_constants: false,
+            },
+        );
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+// 非原生域版本的电路参数：乘法会被拆成大量16位limb的运算，需要更宽的表格
+const BN254_GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 60,
+    num_witness_columns: 0,
+    num_constant_columns: 4,
+    max_allowed_constraint_degree: 4,
+};
+
+const BN254_MAX_VARIABLES: usize = 1 << 22;
+const BN254_MAX_TRACE_LEN: usize = 1 << 17;
+
+fn configure_bn254<
+    F: SmallField,
+    T: CsBuilderImpl<F, T>,
+    GC: GateConfigurationHolder<F>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    // 每个limb都要用16位的范围检查表
+    let builder = builder.allow_lookup(
+        LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+            width: 1,
+            num_repetitions: 10,
+            share_table_id: true,
+        },
+    );
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = ReductionGate::<F, 4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = BooleanConstraintGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = UIntXAddGate::<32>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = UIntXAddGate::<16>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        SelectionGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+    let builder = ZeroCheckGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+        false,
+    );
+    let builder = DotProductGate::<4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+// circomlibjs 的 poseidon([input]) 输出，由 circomlib 的参考参数计算
+const CIRCOMLIB_VECTORS: [(&str, &str); 5] = [
+    (
+        "0",
+        "19014214495641488759237505126948346942972912379615652741039992445865937985820",
+    ),
+    (
+        "1",
+        "18586133768512220936620570745912940619677854269274689475585506675881198879027",
+    ),
+    (
+        "2",
+        "8645981980787649023086883978738420856660271013038108762834452721572614684349",
+    ),
+    (
+        "12345",
+        "4267533774488295900887461483015112262021273608761099826938271132511348470966",
+    ),
+    (
+        "12739402534152800378653876343212971087307846303699842173468443099693139935972",
+        "8367882574794525834975035316774915428508939653865448868012106972434103595628",
+    ),
+];
+
+#[test]
+fn circomlib_poseidon_reference_vectors() {
+    for (input, expected) in CIRCOMLIB_VECTORS {
+        assert_eq!(
+            circomlib_poseidon1(fr_from_decimal(input)),
+            fr_from_decimal(expected)
+        );
+    }
+}
+
+// 在电路中约束 Poseidon(input) 等于 datahash，返回约束是否满足
+fn bn254_datahash_is_satisfied(input: &str, datahash: &str) -> bool {
+    type P = GoldilocksField;
+
+    let params = Arc::new(NonNativeFieldOverU16Params::<Fr, 17>::create());
+    let worker = Worker::new_with_num_threads(8);
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        BN254_GEOMETRY,
+        BN254_MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure_bn254(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(BN254_MAX_VARIABLES));
+
+    cs.add_lookup_table::<RangeCheck16BitsTable, 1>(create_range_check_16_bits_table());
+
+    let out = synthesize_circomlib_poseidon1(&mut cs, fr_from_decimal(input), &params);
+
+    // 约束电路输出等于 circom 的输出
+    let datahash = Bn254Fr::allocated_constant(&mut cs, fr_from_decimal(datahash), &params);
+    Bn254Fr::enforce_equal(&mut cs, &out, &datahash);
+
+    cs.pad_and_shrink();
+    let mut cs = cs.into_assembly::<Global>();
+    cs.check_if_satisfied(&worker)
+}
+
+#[test]
+fn datahash_bn254_matches_circomlib() {
+    for (input, expected) in CIRCOMLIB_VECTORS.iter().take(3) {
+        assert!(bn254_datahash_is_satisfied(input, expected));
+    }
+}
+
+#[test]
+fn datahash_bn254_rejects_wrong_datahash() {
+    // 用另一个输入的 datahash
+    let (input, _) = CIRCOMLIB_VECTORS[0];
+    let (_, wrong) = CIRCOMLIB_VECTORS[1];
+    assert!(!bn254_datahash_is_satisfied(input, wrong));
+}
+
+#[test]
+fn datahash_goldilocks_demo() {
+    type P = GoldilocksField;
+
+    let input = GoldilocksField::from_u64_unchecked(12345);
+    let expected = native_goldilocks_datahash(input);
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GOLDILOCKS_GEOMETRY,
+        128,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure_goldilocks(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(512));
+
+    synthesize_goldilocks_datahash(&mut cs, input);
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(1);
+    let cs = cs.into_assembly::<Global>();
+
+    let proof_config = ProofConfig {
+        fri_lde_factor: 16,
+        pow_bits: 0,
+        merkle_tree_cap_size: 4,
+        ..Default::default()
+    };
+
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    assert_eq!(proof.public_inputs, expected.to_vec());
+
+    let builder_impl = CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(
+        GOLDILOCKS_GEOMETRY,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure_goldilocks(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
+
+#[test]
+fn datahash_bn254_demo() {
+    type P = GoldilocksField;
+
+    let (input, expected) = CIRCOMLIB_VECTORS[1];
+    let params = Arc::new(NonNativeFieldOverU16Params::<Fr, 17>::create());
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        BN254_GEOMETRY,
+        BN254_MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure_bn254(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(BN254_MAX_VARIABLES));
+
+    cs.add_lookup_table::<RangeCheck16BitsTable, 1>(create_range_check_16_bits_table());
+
+    let out = synthesize_circomlib_poseidon1(&mut cs, fr_from_decimal(input), &params);
+    expose_bn254_output(&mut cs, &out);
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(8);
+    let cs = cs.into_assembly::<Global>();
+
+    let proof_config = ProofConfig {
+        fri_lde_factor: 16,
+        pow_bits: 0,
+        merkle_tree_cap_size: 4,
+        ..Default::default()
+    };
+
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    // public input 是 circom 输出的 16 位limb，低位在前
+    let public_limbs: Vec<u64> = proof
+        .public_inputs
+        .iter()
+        .map(|x| x.as_u64_reduced())
+        .collect();
+    assert_eq!(public_limbs, fr_to_u16_limbs(fr_from_decimal(expected)));
+
+    let builder_impl =
+        CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(BN254_GEOMETRY);
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure_bn254(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
@@ -0,0 +1,129 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use boojum::{cs::implementations::prover::ProofConfig, worker::Worker};
+use common::{
+    demos::{DemoCircuit, FibonacciDemo, LookupDemo},
+    harness::{prove_and_verify_production, HashConfiguration, PowConfiguration, ProofProfile},
+    security::{
+        cheapest_config, estimate_security, min_fri_lde_factor, num_fri_queries, quotient_degree,
+        MIN_PRODUCTION_SECURITY_BITS,
+    },
+};
+
+#[test]
+fn demo_config_security() {
+    let demo = FibonacciDemo::default();
+
+    for (name, proof_config) in [
+        ("demo", ProofProfile::Demo.proof_config()),
+        ("production", ProofProfile::Production.proof_config()),
+    ] {
+        let estimate = estimate_security(&proof_config, &demo.geometry(), demo.max_trace_len());
+
+        // 查询次数按security_level计算，conjectured安全性不会低于它
+        assert!(
+            estimate.conjectured_query_bits >= proof_config.security_level as f64,
+            "{}",
+            name
+        );
+        assert!(
+            estimate.proven_bits <= estimate.conjectured_bits,
+            "{}",
+            name
+        );
+    }
+
+    // lde因子16，没有grinding：每次查询4比特
+    let demo_config = ProofProfile::Demo.proof_config();
+    assert_eq!(num_fri_queries(&demo_config), 25);
+
+    // grinding可以减少查询次数
+    let with_pow = ProofConfig {
+        pow_bits: 20,
+        ..demo_config
+    };
+    assert_eq!(num_fri_queries(&with_pow), 20);
+}
+
+#[test]
+fn field_size_limits_security() {
+    let demo = LookupDemo::default();
+    let proof_config = ProofConfig {
+        fri_lde_factor: 16,
+        security_level: 200,
+        ..Default::default()
+    };
+
+    // 查询再多，安全性也不会超过扩域大小的限制
+    let estimate = estimate_security(&proof_config, &demo.geometry(), 1 << 20);
+    assert!(estimate.conjectured_query_bits >= 200.0);
+    assert!(estimate.conjectured_bits < 128.0);
+    assert_eq!(estimate.conjectured_bits, estimate.conjectured_field_bits);
+}
+
+// 与gate_report中的quotient次数相同：约束次数减1，向上取2的幂
+#[test]
+fn quotient_degree_of_constraint_degree() {
+    for (degree, expected) in [(1, 1), (2, 1), (3, 2), (4, 4), (5, 4), (8, 8), (9, 8)] {
+        assert_eq!(quotient_degree(degree), expected, "degree {}", degree);
+    }
+}
+
+#[test]
+fn cheapest_config_meets_target() {
+    for geometry in [
+        FibonacciDemo::default().geometry(),
+        LookupDemo::default().geometry(),
+    ] {
+        assert_eq!(
+            min_fri_lde_factor(&geometry),
+            quotient_degree(geometry.max_allowed_constraint_degree)
+        );
+
+        for target_bits in [80, 100, 120] {
+            let (proof_config, estimate) = cheapest_config(
+                &geometry,
+                FibonacciDemo::default().max_trace_len(),
+                target_bits,
+                4,
+            )
+            .expect("没有满足目标的配置");
+            assert!(estimate.conjectured_bits >= target_bits as f64);
+            assert!(proof_config.fri_lde_factor >= min_fri_lde_factor(&geometry));
+        }
+    }
+}
+
+#[test]
+fn production_proofs_below_threshold_are_refused() {
+    let worker = Worker::new_with_num_threads(1);
+    let demo = FibonacciDemo::default();
+
+    let weak_config = ProofConfig {
+        fri_lde_factor: 2,
+        security_level: 40,
+        ..Default::default()
+    };
+    let result = prove_and_verify_production(
+        &demo,
+        HashConfiguration::Poseidon,
+        PowConfiguration::None,
+        &worker,
+        weak_config,
+        MIN_PRODUCTION_SECURITY_BITS,
+    );
+    assert!(result.is_err());
+
+    let report = prove_and_verify_production(
+        &demo,
+        HashConfiguration::Poseidon,
+        PowConfiguration::Blake2s,
+        &worker,
+        ProofProfile::Production.proof_config(),
+        MIN_PRODUCTION_SECURITY_BITS,
+    )
+    .expect("Production配置应当满足最低安全要求");
+    assert!(report.is_valid);
+}
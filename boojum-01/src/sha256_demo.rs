@@ -0,0 +1,395 @@
+#![feature(allocator_api)]
+use std::alloc::Global;
+
+use boojum::{
+    algebraic_props::{round_function::AbsorptionModeOverwrite, sponge::GoldilocksPoseidonSponge},
+    config::DevCSConfig,
+    cs::{
+        cs_builder::{new_builder, CsBuilder, CsBuilderImpl},
+        cs_builder_reference::CsReferenceImplementationBuilder,
+        cs_builder_verifier::CsVerifierBuilder,
+        gates::{
+            BooleanConstraintGate, ConstantsAllocatorGate, DotProductGate,
+            FmaGateInBaseFieldWithoutConstant, NopGate, PublicInputGate, ReductionGate,
+            SelectionGate, UIntXAddGate, ZeroCheckGate,
+        },
+        implementations::{
+            pow::NoPow, prover::ProofConfig, transcript::GoldilocksPoisedonTranscript,
+        },
+        traits::{cs::ConstraintSystem, gate::GatePlacementStrategy},
+        CSGeometry, GateConfigurationHolder, LookupParameters, StaticToolboxHolder,
+    },
+    dag::CircuitResolverOpts,
+    field::{
+        goldilocks::{GoldilocksExt2, GoldilocksField},
+        SmallField, U64Representable,
+    },
+    gadgets::{
+        num::Num,
+        sha256::round_function::round_function_over_uint32,
+        tables::{
+            create_4bit_chunk_split_table, create_ch4_table, create_maj4_table,
+            create_tri_xor_table, create_xor8_table, Ch4Table, Maj4Table, Split4BitChunkTable,
+            TriXor4Table, Xor8Table,
+        },
+        u32::UInt32,
+        u8::UInt8,
+    },
+    worker::Worker,
+};
+
+// SHA-256 的初始状态 H(0)，FIPS 180-4 5.3.3
+const SHA256_INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const SHA256_BLOCK_SIZE: usize = 64;
+
+// 填充后的消息长度：消息 + 0x80 + 若干0 + 8字节的比特长度，凑成64字节的整数倍
+fn padded_len(message_len: usize) -> usize {
+    (message_len + 1 + 8 + SHA256_BLOCK_SIZE - 1) / SHA256_BLOCK_SIZE * SHA256_BLOCK_SIZE
+}
+
+// 在电路中填充消息，FIPS 180-4 5.1.1
+// 消息长度在电路构建时就确定，所以填充的字节都是常量
+fn sha256_pad<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    message: &[UInt8<F>],
+) -> Vec<UInt8<F>> {
+    let total_len = padded_len(message.len());
+    let bit_len = (message.len() as u64) * 8;
+
+    let mut padded = message.to_vec();
+    padded.push(UInt8::allocated_constant(cs, 0x80));
+    while padded.len() < total_len - 8 {
+        padded.push(UInt8::allocated_constant(cs, 0));
+    }
+    for byte in bit_len.to_be_bytes() {
+        padded.push(UInt8::allocated_constant(cs, byte));
+    }
+
+    padded
+}
+
+// SHA-256 gadget：返回摘要（8个大端UInt32字），以及每次压缩函数占用的行数
+fn sha256<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    message: &[UInt8<F>],
+) -> ([UInt32<F>; 8], Vec<usize>) {
+    let padded = sha256_pad(cs, message);
+
+    let mut state = SHA256_INITIAL_STATE.map(|word| UInt32::allocated_constant(cs, word));
+    let mut rows_per_block = Vec::with_capacity(padded.len() / SHA256_BLOCK_SIZE);
+
+    for block in padded.chunks_exact(SHA256_BLOCK_SIZE) {
+        let row_before = cs.next_available_row();
+
+        // 消息块按大端拼成16个字
+        let words: [UInt32<F>; 16] = std::array::from_fn(|i| {
+            let bytes: [UInt8<F>; 4] = block[4 * i..4 * i + 4].try_into().unwrap();
+            UInt32::from_be_bytes(cs, bytes)
+        });
+
+        // 64轮压缩：xor/ch/maj通过4比特分块的查找表计算，循环移位用拆分表对齐到4比特边界
+        round_function_over_uint32(cs, &mut state, &words);
+
+        rows_per_block.push(cs.next_available_row() - row_before);
+    }
+
+    (state, rows_per_block)
+}
+
+// 每次压缩函数占用的行数，以及整个电路的行数
+#[derive(Clone, Debug, Default)]
+struct RowReport {
+    rows_per_block: Vec<usize>,
+    total_rows: usize,
+}
+
+impl std::fmt::Display for RowReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, rows) in self.rows_per_block.iter().enumerate() {
+            writeln!(f, "compression {}: {} rows", i, rows)?;
+        }
+        write!(f, "total: {} rows", self.total_rows)
+    }
+}
+
+// 证明知道 preimage，使得 SHA-256(preimage) == claimed_digest
+// preimage 是witness，claimed_digest（8个字）是public input
+fn synthesize_preimage_knowledge<F: SmallField, CS: ConstraintSystem<F>>(
+    cs: &mut CS,
+    preimage: &[u8],
+    claimed_digest: [u32; 8],
+) -> RowReport {
+    // allocate_checked会通过xor8查找表检查每个字节的范围
+    let message: Vec<UInt8<F>> = preimage
+        .iter()
+        .map(|byte| UInt8::allocate_checked(cs, *byte))
+        .collect();
+
+    let (digest, rows_per_block) = sha256(cs, &message);
+
+    for (computed, claimed) in digest.iter().zip(claimed_digest.iter()) {
+        let claimed = UInt32::allocate_checked(cs, *claimed);
+        let gate = PublicInputGate::new(claimed.get_variable());
+        gate.add_to_cs(cs);
+
+        Num::enforce_equal(cs, &computed.into_num(), &claimed.into_num());
+    }
+
+    RowReport {
+        rows_per_block,
+        total_rows: cs.next_available_row(),
+    }
+}
+
+fn add_sha256_tables<F: SmallField, CS: ConstraintSystem<F>>(cs: &mut CS) {
+    let table = create_xor8_table();
+    cs.add_lookup_table::<Xor8Table, 3>(table);
+    let table = create_tri_xor_table();
+    cs.add_lookup_table::<TriXor4Table, 4>(table);
+    let table = create_ch4_table();
+    cs.add_lookup_table::<Ch4Table, 4>(table);
+    let table = create_maj4_table();
+    cs.add_lookup_table::<Maj4Table, 4>(table);
+    let table = create_4bit_chunk_split_table::<F, 1>();
+    cs.add_lookup_table::<Split4BitChunkTable<1>, 4>(table);
+    let table = create_4bit_chunk_split_table::<F, 2>();
+    cs.add_lookup_table::<Split4BitChunkTable<2>, 4>(table);
+}
+
+// 设置电路参数
+const GEOMETRY: CSGeometry = CSGeometry {
+    num_columns_under_copy_permutation: 60,
+    num_witness_columns: 0,
+    num_constant_columns: 8,
+    max_allowed_constraint_degree: 4,
+};
+
+const MAX_VARIABLES: usize = 1 << 20; // variable数量上限
+const MAX_TRACE_LEN: usize = 1 << 20; // 电路表格的行数上限，要能放下所有查找表（与boojum的SHA-256测试相同）
+
+// 配置cs的函数
+fn configure<
+    F: SmallField,
+    T: CsBuilderImpl<F, T>,
+    GC: GateConfigurationHolder<F>,
+    TB: StaticToolboxHolder,
+>(
+    builder: CsBuilder<T, F, GC, TB>,
+) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+    // 所有查找表都是3或4列，统一用宽度4的专用列
+    let builder = builder.allow_lookup(
+        LookupParameters::UseSpecializedColumnsWithTableIdAsConstant {
+            width: 4,
+            num_repetitions: 8,
+            share_table_id: true,
+        },
+    );
+    let builder = ConstantsAllocatorGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = FmaGateInBaseFieldWithoutConstant::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = ReductionGate::<F, 4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = BooleanConstraintGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    // UInt32的模加
+    let builder = UIntXAddGate::<32>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = UIntXAddGate::<16>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        SelectionGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+    let builder = ZeroCheckGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+        false,
+    );
+    let builder = DotProductGate::<4>::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder = PublicInputGate::configure_builder(
+        builder,
+        GatePlacementStrategy::UseGeneralPurposeColumns,
+    );
+    let builder =
+        NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+
+    builder
+}
+
+fn digest_from_hex(hex: &str) -> [u32; 8] {
+    std::array::from_fn(|i| u32::from_str_radix(&hex[8 * i..8 * i + 8], 16).unwrap())
+}
+
+// FIPS 180-4 的示例：空消息、"abc"（一个块）、448比特消息（填充后两个块）、896比特消息（两个块）
+const FIPS_180_4_VECTORS: [(&str, &str); 4] = [
+    (
+        "",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    ),
+    (
+        "abc",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    ),
+    (
+        "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    ),
+    (
+        "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+        "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+    ),
+];
+
+// 电路是否满足，同时返回每次压缩占用的行数
+fn preimage_is_satisfied(preimage: &[u8], claimed_digest: [u32; 8]) -> (bool, RowReport) {
+    type P = GoldilocksField;
+
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    add_sha256_tables(&mut cs);
+    let report = synthesize_preimage_knowledge(&mut cs, preimage, claimed_digest);
+
+    cs.pad_and_shrink();
+
+    let worker = Worker::new_with_num_threads(8);
+    let mut cs = cs.into_assembly::<Global>();
+    (cs.check_if_satisfied(&worker), report)
+}
+
+#[test]
+fn sha256_fips_180_4_vectors() {
+    for (message, expected) in FIPS_180_4_VECTORS {
+        let digest = digest_from_hex(expected);
+        let (is_satisfied, report) = preimage_is_satisfied(message.as_bytes(), digest);
+        assert!(is_satisfied, "message {:?}", message);
+        assert_eq!(
+            report.rows_per_block.len(),
+            padded_len(message.len()) / SHA256_BLOCK_SIZE
+        );
+
+        // 错误的摘要不能满足约束
+        let mut wrong_digest = digest;
+        wrong_digest[7] ^= 1;
+        let (is_satisfied, _) = preimage_is_satisfied(message.as_bytes(), wrong_digest);
+        assert!(is_satisfied == false);
+    }
+}
+
+#[test]
+fn sha256_rows_per_compression() {
+    // 150字节的消息填充后是3个块
+    let message = [b'a'; 150];
+    let digest =
+        digest_from_hex("7595af82ae2fa59cd9bf3b4405d31c69b98de71fed5945fd777d8ab3b393a85f");
+    let (is_satisfied, report) = preimage_is_satisfied(&message, digest);
+    assert!(is_satisfied);
+
+    // 第一次压缩还要分配之后共用的常量，之后每个块的电路完全相同
+    let rows = &report.rows_per_block;
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[1], rows[2]);
+    assert!(rows[0] >= rows[1]);
+    assert!(rows.iter().sum::<usize>() <= report.total_rows);
+
+    let lines: Vec<String> = report.to_string().lines().map(String::from).collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[2], format!("compression 2: {} rows", rows[2]));
+    assert_eq!(lines[3], format!("total: {} rows", report.total_rows));
+}
+
+#[test]
+fn sha256_preimage_demo() {
+    type P = GoldilocksField;
+
+    // 两个块的消息
+    let (preimage, expected) = FIPS_180_4_VECTORS[2];
+    let digest = digest_from_hex(expected);
+
+    // cs builder: 约束系统的工厂类
+    let builder_impl = CsReferenceImplementationBuilder::<GoldilocksField, P, DevCSConfig>::new(
+        GEOMETRY,
+        MAX_TRACE_LEN,
+    );
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let mut cs = builder.build(CircuitResolverOpts::new(MAX_VARIABLES));
+
+    add_sha256_tables(&mut cs);
+    let report = synthesize_preimage_knowledge(&mut cs, preimage.as_bytes(), digest);
+    assert_eq!(report.rows_per_block.len(), 2);
+    assert_eq!(report.total_rows, cs.next_available_row());
+
+    // optional
+    cs.pad_and_shrink();
+
+    // 设置线程数量
+    let worker = Worker::new_with_num_threads(8);
+    let cs = cs.into_assembly::<Global>();
+
+    // FRI 的 LDE 因子
+    let lde_factor_to_use = 16;
+    let proof_config = ProofConfig {
+        fri_lde_factor: lde_factor_to_use,
+        pow_bits: 0,
+        merkle_tree_cap_size: 16,
+        ..Default::default()
+    };
+
+    // 使用prove_one_shot同时生成proof和vk，在生产环境不建议这样使用
+    let (proof, vk) = cs.prove_one_shot::<
+        GoldilocksExt2,
+        GoldilocksPoisedonTranscript,
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        NoPow,
+    >(&worker, proof_config, ());
+
+    // verifier只看到摘要，看不到preimage
+    let public_digest: Vec<GoldilocksField> = digest
+        .iter()
+        .map(|word| GoldilocksField::from_u64_unchecked(*word as u64))
+        .collect();
+    assert_eq!(proof.public_inputs, public_digest);
+
+    let builder_impl =
+        CsVerifierBuilder::<GoldilocksField, GoldilocksExt2>::new_from_parameters(GEOMETRY);
+    let builder = new_builder::<_, GoldilocksField>(builder_impl);
+
+    let builder = configure(builder);
+    let verifier = builder.build(());
+
+    let is_valid = verifier.verify::<
+        GoldilocksPoseidonSponge<AbsorptionModeOverwrite>,
+        GoldilocksPoisedonTranscript,
+        NoPow
+    >(
+        (),
+        &vk,
+        &proof,
+    );
+
+    assert!(is_valid);
+}
@@ -0,0 +1,108 @@
+#![feature(allocator_api)]
+
+// 修改合法的proof和vk，检查verifier拒绝所有的修改（见common/tampering.rs）
+
+mod common;
+
+use boojum::worker::Worker;
+use common::{
+    demos::{visit_all_demos, DemoCircuit, DemoVisitor, FibonacciDemo},
+    tampering::{tamper, Outcome, TamperReport},
+};
+
+fn report<D: DemoCircuit>(demo: &D) -> TamperReport {
+    let worker = Worker::new_with_num_threads(8);
+    tamper(demo, &worker)
+}
+
+struct TamperAll {
+    reports: Vec<TamperReport>,
+}
+
+impl DemoVisitor for TamperAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        self.reports.push(report(demo));
+    }
+}
+
+#[test]
+fn every_mutation_is_rejected_for_all_demos() {
+    let mut visitor = TamperAll { reports: vec![] };
+    visit_all_demos(&mut visitor);
+    assert_eq!(visitor.reports.len(), 6);
+
+    for report in &visitor.reports {
+        // 接受和panic都是失败
+        assert!(report.failures().is_empty(), "{}", report);
+
+        // 每个demo都覆盖了proof的每一部分
+        let coverage = report.coverage();
+        for field in [
+            "witness_oracle_cap",
+            "stage_2_oracle_cap",
+            "quotient_oracle_cap",
+            "final_fri_monomials",
+            "values_at_z",
+            "values_at_z_omega",
+            "values_at_0",
+            "fri_base_oracle_cap",
+            "witness_query.leaf_elements",
+            "witness_query.proof",
+            "stage_2_query.leaf_elements",
+            "stage_2_query.proof",
+            "quotient_query.leaf_elements",
+            "quotient_query.proof",
+            "setup_query.leaf_elements",
+            "setup_query.proof",
+            "fri_queries[0].leaf_elements",
+            "queries_per_fri_repetition",
+            "pow_challenge",
+            "proof_config",
+            "vk.setup_merkle_tree_cap",
+        ] {
+            let coverage = coverage.get(field).copied().unwrap_or_default();
+            assert!(coverage.total > 0, "{}: {} not covered", report.demo, field);
+            assert_eq!(
+                coverage.rejected(),
+                coverage.total,
+                "{}: {}",
+                report.demo,
+                field
+            );
+        }
+    }
+}
+
+#[test]
+fn fibonacci_public_input_mutations() {
+    let fibonacci = report(&FibonacciDemo::default());
+
+    let public_inputs: Vec<_> = fibonacci
+        .results
+        .iter()
+        .filter(|result| result.field == "public_inputs")
+        .collect();
+    // 修改唯一的public input，增加一个，删除一个
+    assert_eq!(public_inputs.len(), 3);
+    assert!(public_inputs
+        .iter()
+        .all(|result| result.outcome.is_rejected()));
+
+    // check_proof不检查nonce，只能由verifier拒绝
+    let pow_challenge = fibonacci
+        .results
+        .iter()
+        .filter(|result| result.field == "pow_challenge");
+    assert!(pow_challenge.all(|result| result.outcome == Outcome::RejectedByVerifier));
+
+    // 修改proof中的证明参数，使较少的查询或者没有grinding的proof看起来是合法的
+    let proof_config: Vec<_> = fibonacci
+        .results
+        .iter()
+        .filter(|result| result.field == "proof_config")
+        .collect();
+    assert_eq!(proof_config.len(), 4);
+    assert!(proof_config
+        .iter()
+        .all(|result| result.outcome.is_rejected()));
+}
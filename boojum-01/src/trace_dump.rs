@@ -0,0 +1,82 @@
+#![feature(allocator_api)]
+
+// 导出demo的电路表格，CSV和HTML写到临时目录中，每个测试使用自己的子目录
+
+mod common;
+
+use boojum::{field::U64Representable, worker::Worker};
+use common::{
+    demos::{visit_all_demos, DemoCircuit, DemoVisitor, FibonacciDemo, LookupDemo},
+    harness::synthesize_assembly,
+    trace::{extract_trace, TraceTable},
+};
+
+fn dump<D: DemoCircuit>(demo: &D, test: &str) -> TraceTable {
+    let worker = Worker::new_with_num_threads(8);
+    let cs = synthesize_assembly(demo);
+    let table = extract_trace(&cs, &worker);
+
+    let dir = std::env::temp_dir().join("boojum_traces").join(test);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("{}.csv", demo.name())), table.to_csv()).unwrap();
+    std::fs::write(
+        dir.join(format!("{}.html", demo.name())),
+        table.to_html(demo.name()),
+    )
+    .unwrap();
+
+    table
+}
+
+struct DumpAll;
+
+impl DemoVisitor for DumpAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        let table = dump(demo, "dump_all_demos");
+        assert!(table.rows.len().is_power_of_two());
+
+        // CSV：表头加上每一行
+        let csv = table.to_csv();
+        assert_eq!(csv.lines().count(), table.rows.len() + 1);
+    }
+}
+
+#[test]
+fn dump_all_demos() {
+    visit_all_demos(&mut DumpAll);
+}
+
+#[test]
+fn fibonacci_trace() {
+    let demo = FibonacciDemo::default();
+    let table = dump(&demo, "fibonacci_trace");
+
+    // public input（34）出现在表格中
+    assert!(table.rows.iter().any(|row| row
+        .variables
+        .iter()
+        .flatten()
+        .any(|(_, value)| value.as_u64_reduced() == demo.out)));
+
+    // 常量1被每一步的Fma gate使用，构成一个较长的环
+    assert!(table.copy_cycles().values().any(|len| *len >= demo.n - 2));
+
+    let html = table.to_html(demo.name());
+    for name in &table.gate_names {
+        assert!(html.contains(&name.replace('<', "&lt;").replace('>', "&gt;")));
+    }
+    assert!(!html.contains("specialized columns"));
+}
+
+#[test]
+fn lookup_trace_has_specialized_columns() {
+    let table = dump(
+        &LookupDemo::default(),
+        "lookup_trace_has_specialized_columns",
+    );
+    assert!(table
+        .rows
+        .iter()
+        .any(|row| row.specialized.iter().any(|cell| cell.is_some())));
+    assert!(table.to_html("lookup_demo").contains("specialized columns"));
+}
@@ -0,0 +1,57 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use boojum::worker::Worker;
+use common::{
+    demos::{visit_all_demos, DemoCircuit, DemoVisitor},
+    harness::{
+        prove_and_verify, HashConfiguration, PowConfiguration, ProofProfile, ProveVerifyReport,
+    },
+};
+
+struct CompareTranscripts {
+    worker: Worker,
+    reports: Vec<ProveVerifyReport>,
+}
+
+impl DemoVisitor for CompareTranscripts {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        for hash in [HashConfiguration::Poseidon, HashConfiguration::Blake2s] {
+            let report = prove_and_verify(
+                demo,
+                hash,
+                PowConfiguration::None,
+                &self.worker,
+                ProofProfile::Demo.proof_config(),
+            );
+            self.reports.push(report);
+        }
+    }
+}
+
+// 用Poseidon和Blake2s两种transcript/树哈希证明所有demo，比较proof大小和时间
+#[test]
+fn compare_poseidon_and_blake2s_transcripts() {
+    let mut comparison = CompareTranscripts {
+        worker: Worker::new_with_num_threads(1),
+        reports: vec![],
+    };
+    visit_all_demos(&mut comparison);
+
+    println!(
+        "{:<24} {:<10} {:>12} {:>12} {:>12}",
+        "demo", "hash", "proof bytes", "prove", "verify"
+    );
+    for report in comparison.reports.iter() {
+        println!(
+            "{:<24} {:<10} {:>12} {:>12?} {:>12?}",
+            report.demo,
+            format!("{:?}", report.hash),
+            report.proof_size,
+            report.prove_time,
+            report.verify_time
+        );
+        assert!(report.is_valid, "{} {:?}", report.demo, report.hash);
+    }
+}
@@ -0,0 +1,153 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use boojum::{
+    cs::{
+        cs_builder::{CsBuilder, CsBuilderImpl},
+        gates::{
+            ConstantAllocatableCS, FmaGateInBaseFieldWithoutConstant,
+            FmaGateInBaseWithoutConstantParams,
+        },
+        traits::cs::ConstraintSystem,
+        CSGeometry, GateConfigurationHolder, StaticToolboxHolder,
+    },
+    field::{Field, U64Representable},
+    worker::Worker,
+};
+use common::{
+    demos::{visit_all_demos, BooleanDemo, DemoCircuit, DemoVisitor, FibonacciDemo, F},
+    underconstrained::{apply_free_witness, find_underconstrained, UnderconstrainedReport},
+    wiring_graph::build_wiring_graph,
+};
+
+fn report<D: DemoCircuit>(demo: &D) -> UnderconstrainedReport {
+    let worker = Worker::new_with_num_threads(8);
+    find_underconstrained(demo, &worker, 8)
+}
+
+// simple_fibonacci之后，分配一个没有使用的witness，以及一个只出现在系数为0的位置上的witness
+struct FibonacciWithUnboundWitness(FibonacciDemo);
+
+impl DemoCircuit for FibonacciWithUnboundWitness {
+    fn name(&self) -> &'static str {
+        "fibonacci_with_unbound_witness"
+    }
+
+    fn geometry(&self) -> CSGeometry {
+        self.0.geometry()
+    }
+
+    fn max_variables(&self) -> usize {
+        self.0.max_variables()
+    }
+
+    fn max_trace_len(&self) -> usize {
+        self.0.max_trace_len()
+    }
+
+    fn configure<
+        T: CsBuilderImpl<F, T>,
+        GC: GateConfigurationHolder<F>,
+        TB: StaticToolboxHolder,
+    >(
+        builder: CsBuilder<T, F, GC, TB>,
+    ) -> CsBuilder<T, F, impl GateConfigurationHolder<F>, impl StaticToolboxHolder> {
+        FibonacciDemo::configure(builder)
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(&self, cs: &mut CS) {
+        self.0.synthesize(cs);
+
+        let one = cs.allocate_constant(F::ONE);
+        let _unused = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(7));
+        let y = cs.alloc_single_variable_from_witness(F::from_u64_unchecked(5));
+
+        // 0 * y * y + 1 * 1 = 1
+        let gate = FmaGateInBaseFieldWithoutConstant {
+            params: FmaGateInBaseWithoutConstantParams {
+                coeff_for_quadtaric_part: F::ZERO,
+                linear_term_coeff: F::ONE,
+            },
+            quadratic_part: (y, y),
+            linear_part: one,
+            rhs_part: one,
+        };
+        gate.add_to_cs(cs);
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        self.0.public_inputs()
+    }
+}
+
+struct ReportAll;
+
+impl DemoVisitor for ReportAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        let worker = Worker::new_with_num_threads(8);
+        let report = report(demo);
+        // 重新写入报告的值：仍然通过check_if_satisfied，public input不变
+        for witness in &report.free {
+            let mut cs = apply_free_witness(demo, witness);
+            assert!(cs.check_if_satisfied(&worker), "{}", demo.name());
+
+            let graph = build_wiring_graph(&cs);
+            assert_eq!(graph.values[&witness.variable], witness.replacement);
+            let public_inputs: Vec<F> = graph
+                .public_inputs
+                .iter()
+                .map(|variable| graph.values[variable])
+                .collect();
+            assert_eq!(public_inputs, demo.public_inputs(), "{}", demo.name());
+        }
+    }
+}
+
+#[test]
+fn report_all_demos() {
+    visit_all_demos(&mut ReportAll);
+}
+
+#[test]
+fn fibonacci_is_fully_constrained() {
+    let fibonacci = report(&FibonacciDemo::default());
+    assert!(fibonacci.unchecked_gates.is_empty());
+    assert!(fibonacci.is_fully_constrained());
+}
+
+#[test]
+fn boolean_demo_output_is_not_bound() {
+    let boolean = report(&BooleanDemo::default());
+    // b和one都是witness，b = 0, one = 0同样满足所有约束
+    assert!(boolean.unplaced.is_empty());
+    assert!(boolean.irrelevant.is_empty());
+    assert_eq!(boolean.free.len(), 1);
+
+    let witness = &boolean.free[0];
+    assert_eq!(witness.original, F::ONE);
+    assert_eq!(witness.replacement, F::ZERO);
+    assert_eq!(witness.changed.len(), 1);
+    assert_eq!(witness.changed[0].1, F::ZERO);
+}
+
+#[test]
+fn unbound_witnesses_are_reported() {
+    let unbound = report(&FibonacciWithUnboundWitness(FibonacciDemo::default()));
+    assert_eq!(unbound.unplaced.len(), 1);
+
+    assert_eq!(unbound.irrelevant.len(), 1);
+    let y = &unbound.irrelevant[0];
+    assert_eq!(y.value.as_u64_reduced(), 5);
+    let positions: Vec<usize> = y
+        .occurrences
+        .iter()
+        .map(|occurrence| occurrence.position)
+        .collect();
+    assert_eq!(positions, vec![0, 1]);
+
+    // y可以任意改变，不影响其他variable
+    assert_eq!(unbound.free.len(), 1);
+    assert_eq!(unbound.free[0].variable, y.variable);
+    assert!(unbound.free[0].changed.is_empty());
+}
@@ -0,0 +1,428 @@
+#![feature(allocator_api)]
+
+// 用harness生成的proof测试verifier_api（wasm_verifier.rs使用的验证入口）
+// wasm32下的运行需要wasm-bindgen和wasmtime/浏览器环境，这里在原生环境中测试相同的代码。
+// 能解码但形状不对的proof必须返回Err而不是panic：wasm中panic无法恢复
+
+mod common;
+
+use boojum::{cs::implementations::pow::NoPow, field::U64Representable, worker::Worker};
+use common::{
+    demos::{
+        visit_all_demos, DemoCircuit, DemoVisitor, FibonacciDemo, LookupDemo, SimplePolyDemo, F,
+    },
+    harness::{prove, ProofProfile},
+    recursion::{H, TR},
+    verifier_api::{verify, VerifyError, CIRCUIT_IDS},
+};
+
+fn prove_to_bytes<D: DemoCircuit>(demo: &D) -> (Vec<u8>, Vec<u8>) {
+    let worker = Worker::new_with_num_threads(8);
+    let (proof, vk) = prove::<_, TR, H, NoPow>(demo, &worker, ProofProfile::Demo.proof_config());
+
+    (
+        serde_json::to_vec(&vk).expect("不能序列化verification key"),
+        serde_json::to_vec(&proof).expect("不能序列化proof"),
+    )
+}
+
+struct VerifyAll;
+
+impl DemoVisitor for VerifyAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        assert!(CIRCUIT_IDS.contains(&demo.name()));
+
+        let (vk_bytes, proof_bytes) = prove_to_bytes(demo);
+        let public_inputs = verify(&vk_bytes, &proof_bytes, demo.name()).unwrap();
+        let expected: Vec<u64> = demo
+            .public_inputs()
+            .iter()
+            .map(|el| el.as_u64_reduced())
+            .collect();
+        assert_eq!(public_inputs, expected, "{}", demo.name());
+    }
+}
+
+#[test]
+fn verify_all_demos_from_bytes() {
+    visit_all_demos(&mut VerifyAll);
+}
+
+#[test]
+fn verify_rejects_wrong_inputs() {
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&FibonacciDemo::default());
+
+    assert_eq!(
+        verify(&vk_bytes, &proof_bytes, "sha256"),
+        Err(VerifyError::UnknownCircuit("sha256".to_string()))
+    );
+
+    // vk和电路不一致
+    assert_eq!(
+        verify(&vk_bytes, &proof_bytes, "uint8_demo"),
+        Err(VerifyError::GeometryMismatch)
+    );
+
+    // 另一个电路的vk
+    let (other_vk_bytes, _) = prove_to_bytes(&FibonacciDemo::new(10));
+    assert_eq!(
+        verify(&other_vk_bytes, &proof_bytes, "simple_fibonacci"),
+        Err(VerifyError::VerificationFailed)
+    );
+
+    // 修改public input
+    let mut proof: serde_json::Value = serde_json::from_slice(&proof_bytes).unwrap();
+    let (_, poly_proof_bytes) = prove_to_bytes(&SimplePolyDemo::default());
+    let poly_proof: serde_json::Value = serde_json::from_slice(&poly_proof_bytes).unwrap();
+    proof["public_inputs"] = poly_proof["public_inputs"].clone();
+    let tampered = serde_json::to_vec(&proof).unwrap();
+    assert_eq!(
+        verify(&vk_bytes, &tampered, "simple_fibonacci"),
+        Err(VerifyError::VerificationFailed)
+    );
+
+    // 截断的proof
+    assert!(matches!(
+        verify(
+            &vk_bytes,
+            &proof_bytes[..proof_bytes.len() / 2],
+            "simple_fibonacci"
+        ),
+        Err(VerifyError::InvalidProof(_))
+    ));
+
+    assert_eq!(
+        verify(&vk_bytes, &proof_bytes, "simple_fibonacci"),
+        Ok(vec![F::from_u64_unchecked(34).as_u64_reduced()])
+    );
+}
+
+// 修改JSON之后再验证
+fn verify_modified_circuit(
+    circuit_id: &str,
+    vk_bytes: &[u8],
+    proof_bytes: &[u8],
+    modify_vk: impl FnOnce(&mut serde_json::Value),
+    modify_proof: impl FnOnce(&mut serde_json::Value),
+) -> Result<Vec<u64>, VerifyError> {
+    let mut vk: serde_json::Value = serde_json::from_slice(vk_bytes).unwrap();
+    let mut proof: serde_json::Value = serde_json::from_slice(proof_bytes).unwrap();
+    modify_vk(&mut vk);
+    modify_proof(&mut proof);
+
+    verify(
+        &serde_json::to_vec(&vk).unwrap(),
+        &serde_json::to_vec(&proof).unwrap(),
+        circuit_id,
+    )
+}
+
+fn verify_modified(
+    vk_bytes: &[u8],
+    proof_bytes: &[u8],
+    modify_vk: impl FnOnce(&mut serde_json::Value),
+    modify_proof: impl FnOnce(&mut serde_json::Value),
+) -> Result<Vec<u64>, VerifyError> {
+    verify_modified_circuit(
+        "simple_fibonacci",
+        vk_bytes,
+        proof_bytes,
+        modify_vk,
+        modify_proof,
+    )
+}
+
+// 修改每一次重复中的查询
+fn modify_queries(proof: &mut serde_json::Value, modify: impl Fn(&mut serde_json::Value)) {
+    for queries in proof["queries_per_fri_repetition"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+    {
+        modify(queries);
+    }
+}
+
+fn pop(value: &mut serde_json::Value) {
+    value.as_array_mut().unwrap().pop();
+}
+
+#[test]
+fn verify_rejects_unexpected_config() {
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&FibonacciDemo::default());
+    let config_mismatch = |field: &str| Err(VerifyError::ConfigMismatch(field.to_string()));
+
+    // 降低security_level并删除相应的查询
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| {
+                proof["proof_config"]["security_level"] = 50.into();
+                let queries = proof["queries_per_fri_repetition"].as_array_mut().unwrap();
+                queries.truncate(queries.len() / 2);
+            }
+        ),
+        config_mismatch("security_level")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| proof["proof_config"]["fri_lde_factor"] = 8.into()
+        ),
+        config_mismatch("fri_lde_factor")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| proof["proof_config"]["pow_bits"] = 20.into()
+        ),
+        config_mismatch("pow_bits")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| proof["proof_config"]["fri_folding_schedule"] = serde_json::json!([1, 1])
+        ),
+        config_mismatch("fri_folding_schedule")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |vk| vk["fixed_parameters"]["cap_size"] = 8.into(),
+            |_| {}
+        ),
+        config_mismatch("vk cap_size")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |vk| pop(&mut vk["setup_merkle_tree_cap"]),
+            |_| {}
+        ),
+        config_mismatch("vk setup_merkle_tree_cap")
+    );
+}
+
+#[test]
+fn verify_rejects_malformed_proofs() {
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&FibonacciDemo::default());
+    let malformed = |field: &str| Err(VerifyError::MalformedProof(field.to_string()));
+
+    for cap in [
+        "witness_oracle_cap",
+        "stage_2_oracle_cap",
+        "quotient_oracle_cap",
+        "fri_base_oracle_cap",
+    ] {
+        assert_eq!(
+            verify_modified(
+                &vk_bytes,
+                &proof_bytes,
+                |_| {},
+                |proof| pop(&mut proof[cap])
+            ),
+            malformed(cap)
+        );
+    }
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| pop(&mut proof["public_inputs"])
+        ),
+        malformed("public_inputs")
+    );
+
+    // 缺少查询
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| pop(&mut proof["queries_per_fri_repetition"])
+        ),
+        malformed("queries_per_fri_repetition")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| pop(&mut proof["queries_per_fri_repetition"][3]["fri_queries"])
+        ),
+        malformed("fri_queries")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| pop(&mut proof["queries_per_fri_repetition"][0]["witness_query"]["proof"])
+        ),
+        malformed("witness_query")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| pop(&mut proof["queries_per_fri_repetition"][5]["setup_query"]["leaf_elements"])
+        ),
+        malformed("setup_query")
+    );
+
+    // 没有修改时仍然通过
+    assert!(verify_modified(&vk_bytes, &proof_bytes, |_| {}, |_| {}).is_ok());
+}
+
+// 所有重复都有相同的错误形状时，与第一次重复比较是发现不了的，必须与vk计算出的长度比较
+#[test]
+fn verify_rejects_proofs_shaped_for_another_vk() {
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&FibonacciDemo::default());
+    let malformed = |field: &str| Err(VerifyError::MalformedProof(field.to_string()));
+
+    for values in ["values_at_z", "values_at_z_omega"] {
+        assert_eq!(
+            verify_modified(
+                &vk_bytes,
+                &proof_bytes,
+                |_| {},
+                |proof| pop(&mut proof[values])
+            ),
+            malformed(values)
+        );
+    }
+    // 没有lookup时values_at_0是空的
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| {
+                let value = proof["values_at_z"][0].clone();
+                proof["values_at_0"].as_array_mut().unwrap().push(value);
+            }
+        ),
+        malformed("values_at_0")
+    );
+
+    // 两组系数的长度相同，但与折叠之后的次数不一致
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| {
+                pop(&mut proof["final_fri_monomials"][0]);
+                pop(&mut proof["final_fri_monomials"][1]);
+            }
+        ),
+        malformed("final_fri_monomials")
+    );
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| {
+                let cap = proof["fri_base_oracle_cap"].clone();
+                proof["fri_intermediate_oracles_caps"]
+                    .as_array_mut()
+                    .unwrap()
+                    .push(cap);
+            }
+        ),
+        malformed("fri_intermediate_oracles_caps")
+    );
+
+    for oracle in [
+        "witness_query",
+        "stage_2_query",
+        "quotient_query",
+        "setup_query",
+    ] {
+        assert_eq!(
+            verify_modified(
+                &vk_bytes,
+                &proof_bytes,
+                |_| {},
+                |proof| modify_queries(proof, |queries| pop(&mut queries[oracle]["leaf_elements"]))
+            ),
+            malformed(oracle)
+        );
+    }
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |_| {},
+            |proof| modify_queries(proof, |queries| pop(
+                &mut queries["fri_queries"][0]["leaf_elements"]
+            ))
+        ),
+        malformed("fri_queries")
+    );
+
+    // vk中的quotient次数不能超过lde因子
+    assert_eq!(
+        verify_modified(
+            &vk_bytes,
+            &proof_bytes,
+            |vk| vk["fixed_parameters"]["quotient_degree"] = 32.into(),
+            |_| {}
+        ),
+        Err(VerifyError::ConfigMismatch(
+            "vk quotient_degree".to_string()
+        ))
+    );
+}
+
+#[test]
+fn verify_checks_lookup_shape() {
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&LookupDemo::default());
+    let verify_lookup = |modify_vk: fn(&mut serde_json::Value),
+                         modify_proof: fn(&mut serde_json::Value)| {
+        verify_modified_circuit(
+            "lookup_demo",
+            &vk_bytes,
+            &proof_bytes,
+            modify_vk,
+            modify_proof,
+        )
+    };
+
+    assert!(verify_lookup(|_| {}, |_| {}).is_ok());
+    assert_eq!(
+        verify_lookup(|_| {}, |proof| pop(&mut proof["values_at_0"])),
+        Err(VerifyError::MalformedProof("values_at_0".to_string()))
+    );
+    // multiplicity在witness oracle中
+    assert_eq!(
+        verify_lookup(
+            |_| {},
+            |proof| modify_queries(proof, |queries| pop(
+                &mut queries["witness_query"]["leaf_elements"]
+            ))
+        ),
+        Err(VerifyError::MalformedProof("witness_query".to_string()))
+    );
+    // vk的lookup参数与电路不一致
+    assert_eq!(
+        verify_lookup(
+            |vk| vk["fixed_parameters"]["lookup_parameters"] = "NoLookup".into(),
+            |_| {}
+        ),
+        Err(VerifyError::GeometryMismatch)
+    );
+}
@@ -0,0 +1,86 @@
+// 浏览器中验证demo proof的入口
+//
+// 与其他文件不同，这里不启用allocator_api，也不引入harness（prover需要Global allocator和Worker），
+// 只包含demo的电路配置和verifier_api，可以编译到wasm32-unknown-unknown：
+//   cargo build --target wasm32-unknown-unknown --release
+//   wasm-bindgen --target web ...
+// JS中的调用方式：verify(vkBytes, proofBytes, "simple_fibonacci")，
+// vk和proof是serde_json序列化的字节（与prove_verify_fibonacci.rs中保存的格式相同），
+// 成功时返回public input，失败时抛出错误信息
+//
+// 没有JS胶水代码的环境（wasmtime）使用alloc和verify_raw，见wasm_verifier_wasmtime.rs；
+// 构建方式见README
+
+#[path = "common/demos.rs"]
+#[allow(dead_code)]
+mod demos;
+#[path = "common/verifier_api.rs"]
+mod verifier_api;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+// public input以u64返回，在JS中对应BigUint64Array
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn verify(vk_bytes: &[u8], proof_bytes: &[u8], circuit_id: &str) -> Result<Vec<u64>, String> {
+    verifier_api::verify(vk_bytes, proof_bytes, circuit_id).map_err(|err| err.to_string())
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn circuit_ids() -> Vec<String> {
+    verifier_api::CIRCUIT_IDS
+        .iter()
+        .map(|id| id.to_string())
+        .collect()
+}
+
+/// 为输入分配len字节，返回的指针交给verify_raw，不需要释放
+#[no_mangle]
+pub extern "C" fn alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// 与verify相同，返回0表示proof有效，1表示无效或者输入不对
+///
+/// # Safety
+///
+/// 三个指针都必须是alloc返回的、至少有对应长度的内存
+#[no_mangle]
+pub unsafe extern "C" fn verify_raw(
+    vk_ptr: *const u8,
+    vk_len: usize,
+    proof_ptr: *const u8,
+    proof_len: usize,
+    circuit_id_ptr: *const u8,
+    circuit_id_len: usize,
+) -> u32 {
+    let vk_bytes = std::slice::from_raw_parts(vk_ptr, vk_len);
+    let proof_bytes = std::slice::from_raw_parts(proof_ptr, proof_len);
+    let circuit_id = std::slice::from_raw_parts(circuit_id_ptr, circuit_id_len);
+    let Ok(circuit_id) = std::str::from_utf8(circuit_id) else {
+        return 1;
+    };
+
+    match verify(vk_bytes, proof_bytes, circuit_id) {
+        Ok(_) => 0,
+        Err(_) => 1,
+    }
+}
+
+#[test]
+fn rejects_malformed_input() {
+    assert_eq!(circuit_ids().len(), 6);
+
+    let err = verify(b"{}", b"{}", "simple_fibonacci").unwrap_err();
+    assert!(err.starts_with("cannot decode verification key"));
+
+    let err = verify(b"", b"", "unknown").unwrap_err();
+    assert!(err.starts_with("cannot decode"));
+
+    let id = b"simple_fibonacci";
+    let status = unsafe { verify_raw(b"{}".as_ptr(), 2, b"{}".as_ptr(), 2, id.as_ptr(), id.len()) };
+    assert_eq!(status, 1);
+}
@@ -0,0 +1,137 @@
+#![feature(allocator_api)]
+
+// 在wasmtime中运行编译到wasm32的wasm_verifier.rs，验证harness生成的proof
+// 需要先构建wasm模块（见README），路径可以用BOOJUM_WASM_VERIFIER指定
+// 这里不经过wasm-bindgen的JS胶水代码，直接调用alloc和verify_raw；
+// 模块中wasm-bindgen的import不会被调用，定义成trap即可
+
+mod common;
+
+use boojum::{cs::implementations::pow::NoPow, worker::Worker};
+use common::{
+    demos::{DemoCircuit, FibonacciDemo, LookupDemo},
+    harness::{prove, ProofProfile},
+    recursion::{H, TR},
+};
+use wasmtime::{Engine, Instance, Linker, Module, Store};
+
+const DEFAULT_WASM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/wasm32-unknown-unknown/release/wasm_verifier.wasm"
+);
+
+struct WasmVerifier {
+    store: Store<()>,
+    instance: Instance,
+}
+
+impl WasmVerifier {
+    fn load() -> Self {
+        let path =
+            std::env::var("BOOJUM_WASM_VERIFIER").unwrap_or_else(|_| DEFAULT_WASM_PATH.to_string());
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, &path)
+            .unwrap_or_else(|err| panic!("不能加载{}：{}；先按README构建wasm_verifier", path, err));
+
+        let mut store = Store::new(&engine, ());
+        let mut linker = Linker::new(&engine);
+        linker.define_unknown_imports_as_traps(&module).unwrap();
+        let instance = linker.instantiate(&mut store, &module).unwrap();
+
+        Self { store, instance }
+    }
+
+    // 把输入复制到wasm的内存中，返回指针
+    fn write(&mut self, bytes: &[u8]) -> u32 {
+        let alloc = self
+            .instance
+            .get_typed_func::<u32, u32>(&mut self.store, "alloc")
+            .unwrap();
+        let ptr = alloc.call(&mut self.store, bytes.len() as u32).unwrap();
+        let memory = self.instance.get_memory(&mut self.store, "memory").unwrap();
+        memory.write(&mut self.store, ptr as usize, bytes).unwrap();
+        ptr
+    }
+
+    // 返回verify_raw的结果；wasm中的panic会变成trap，这里直接失败
+    fn verify(&mut self, vk_bytes: &[u8], proof_bytes: &[u8], circuit_id: &str) -> u32 {
+        let vk_ptr = self.write(vk_bytes);
+        let proof_ptr = self.write(proof_bytes);
+        let circuit_id_ptr = self.write(circuit_id.as_bytes());
+
+        let verify_raw = self
+            .instance
+            .get_typed_func::<(u32, u32, u32, u32, u32, u32), u32>(&mut self.store, "verify_raw")
+            .unwrap();
+        verify_raw
+            .call(
+                &mut self.store,
+                (
+                    vk_ptr,
+                    vk_bytes.len() as u32,
+                    proof_ptr,
+                    proof_bytes.len() as u32,
+                    circuit_id_ptr,
+                    circuit_id.len() as u32,
+                ),
+            )
+            .expect("wasm中的verifier不应当trap")
+    }
+}
+
+fn prove_to_bytes<D: DemoCircuit>(demo: &D) -> (Vec<u8>, Vec<u8>) {
+    let worker = Worker::new_with_num_threads(8);
+    let (proof, vk) = prove::<_, TR, H, NoPow>(demo, &worker, ProofProfile::Demo.proof_config());
+
+    (
+        serde_json::to_vec(&vk).unwrap(),
+        serde_json::to_vec(&proof).unwrap(),
+    )
+}
+
+#[test]
+fn wasm_verifier_accepts_valid_proofs() {
+    let mut verifier = WasmVerifier::load();
+
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&FibonacciDemo::default());
+    assert_eq!(
+        verifier.verify(&vk_bytes, &proof_bytes, "simple_fibonacci"),
+        0
+    );
+
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&LookupDemo::default());
+    assert_eq!(verifier.verify(&vk_bytes, &proof_bytes, "lookup_demo"), 0);
+}
+
+#[test]
+fn wasm_verifier_rejects_without_trapping() {
+    let mut verifier = WasmVerifier::load();
+    let (vk_bytes, proof_bytes) = prove_to_bytes(&FibonacciDemo::default());
+
+    // 修改public input
+    let mut proof: serde_json::Value = serde_json::from_slice(&proof_bytes).unwrap();
+    proof["public_inputs"][0] = 35.into();
+    let tampered = serde_json::to_vec(&proof).unwrap();
+    assert_eq!(verifier.verify(&vk_bytes, &tampered, "simple_fibonacci"), 1);
+
+    // 形状不对的proof：原生环境中boojum的verifier会panic
+    let mut proof: serde_json::Value = serde_json::from_slice(&proof_bytes).unwrap();
+    proof["values_at_z"].as_array_mut().unwrap().pop();
+    let malformed = serde_json::to_vec(&proof).unwrap();
+    assert_eq!(
+        verifier.verify(&vk_bytes, &malformed, "simple_fibonacci"),
+        1
+    );
+
+    // 截断的proof、其他电路、未知的电路
+    assert_eq!(
+        verifier.verify(
+            &vk_bytes,
+            &proof_bytes[..proof_bytes.len() / 2],
+            "simple_fibonacci"
+        ),
+        1
+    );
+    assert_eq!(verifier.verify(&vk_bytes, &proof_bytes, "uint8_demo"), 1);
+    assert_eq!(verifier.verify(&vk_bytes, &proof_bytes, "sha256"), 1);
+}
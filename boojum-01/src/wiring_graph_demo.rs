@@ -0,0 +1,145 @@
+#![feature(allocator_api)]
+
+mod common;
+
+use common::{
+    demos::{
+        visit_all_demos, ConstantFibonacciDemo, DemoCircuit, DemoVisitor, FibonacciDemo, LookupDemo,
+    },
+    harness::synthesize_assembly,
+    wiring_graph::{build_wiring_graph, WiringGraph},
+};
+
+fn graph<D: DemoCircuit>(demo: &D) -> WiringGraph {
+    let cs = synthesize_assembly(demo);
+    build_wiring_graph(&cs)
+}
+
+fn count_gates(graph: &WiringGraph, name: &str) -> usize {
+    graph.gates.iter().filter(|gate| gate.gate == name).count()
+}
+
+struct ExportAll;
+
+impl DemoVisitor for ExportAll {
+    fn visit<D: DemoCircuit>(&mut self, demo: &D) {
+        let graph = graph(demo);
+        assert_eq!(graph.public_inputs.len(), demo.public_inputs().len());
+
+        let dir = std::env::temp_dir();
+        std::fs::write(dir.join(format!("{}.dot", demo.name())), graph.to_dot()).unwrap();
+        std::fs::write(
+            dir.join(format!("{}.json", demo.name())),
+            serde_json::to_string_pretty(&graph.to_json()).unwrap(),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn export_all_demos() {
+    visit_all_demos(&mut ExportAll);
+}
+
+#[test]
+fn fibonacci_public_input_subgraph() {
+    let demo = FibonacciDemo::default();
+    let graph = graph(&demo);
+    assert!(graph.unknown_gates.is_empty());
+    assert_eq!(graph.public_inputs.len(), 1);
+
+    // n - 2步加上最后的比较，以及常量1
+    let subgraph = graph.feeding_public_input(0);
+    assert_eq!(
+        count_gates(&subgraph, "FmaGateInBaseFieldWithoutConstant"),
+        demo.n - 1
+    );
+    assert_eq!(count_gates(&subgraph, "ConstantsAllocatorGate"), 1);
+    assert_eq!(subgraph.public_inputs, graph.public_inputs);
+    // 最后的 c * 1 = out 只是检查，out是没有来源的witness
+    let checks: Vec<_> = subgraph.gates.iter().filter(|gate| gate.check).collect();
+    assert_eq!(checks.len(), 1);
+    assert!(checks[0].outputs.is_empty());
+    assert!(checks[0].inputs.contains(&graph.public_inputs[0]));
+
+    // 最开始的两个1和out是没有gate计算的witness
+    let inputs = subgraph
+        .values
+        .keys()
+        .filter(|variable| {
+            !subgraph
+                .gates
+                .iter()
+                .any(|gate| gate.outputs.contains(variable))
+        })
+        .count();
+    assert_eq!(inputs, 3);
+
+    let dot = subgraph.to_dot();
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains("doublecircle"));
+    assert!(dot.contains(&format!("= {}\"", demo.out)));
+
+    let json = subgraph.to_json();
+    assert_eq!(
+        json["variables"].as_array().unwrap().len(),
+        subgraph.values.len()
+    );
+    assert_eq!(
+        json["gates"].as_array().unwrap().len(),
+        subgraph.gates.len()
+    );
+    assert_eq!(json["public_inputs"][0], subgraph.public_inputs[0]);
+}
+
+#[test]
+fn subgraph_of_intermediate_variable() {
+    let demo = FibonacciDemo::default();
+    let graph = graph(&demo);
+
+    // 第一步的结果只依赖最开始的两个witness和常量1
+    let first_step = graph
+        .gates
+        .iter()
+        .find(|gate| gate.gate == "FmaGateInBaseFieldWithoutConstant")
+        .unwrap();
+    let subgraph = graph.feeding(&first_step.outputs);
+    assert_eq!(
+        count_gates(&subgraph, "FmaGateInBaseFieldWithoutConstant"),
+        1
+    );
+    assert_eq!(subgraph.values.len(), 4);
+    assert!(subgraph.public_inputs.is_empty());
+}
+
+#[test]
+fn constant_output_is_only_checked() {
+    let graph = graph(&ConstantFibonacciDemo::default());
+    let checks: Vec<_> = graph.gates.iter().filter(|gate| gate.check).collect();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].gate, "FmaGateInBaseFieldWithoutConstant");
+
+    // 每个variable最多有一个来源
+    let mut outputs: Vec<u32> = graph
+        .gates
+        .iter()
+        .flat_map(|gate| gate.outputs.iter().copied())
+        .collect();
+    let len = outputs.len();
+    outputs.sort();
+    outputs.dedup();
+    assert_eq!(outputs.len(), len);
+}
+
+#[test]
+fn lookups_are_reported_as_unknown() {
+    let graph = graph(&LookupDemo::default());
+    assert!(graph
+        .unknown_gates
+        .iter()
+        .any(|gate| gate.starts_with("lookup")));
+    assert!(!graph.to_json()["unknown_gates"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+}